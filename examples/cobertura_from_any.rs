@@ -0,0 +1,28 @@
+use std::env;
+use std::io::stdout;
+
+use coverage_formats::cobertura::CoberturaReport;
+use coverage_formats::coverage::CoverageReport;
+use coverage_formats::format::open_any;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let path = env::args()
+        .nth(1)
+        .expect("usage: cobertura_from_any <coverage file or GOCOVERDIR>");
+
+    // Go profiles, JaCoCo XML, LCOV, ... for GitLab and Azure DevOps
+    let coverage = open_any(path)?;
+    let report = CoberturaReport::from_coverage(&coverage, String::new(), 0);
+    let mut written = vec![];
+    report.write_to(&mut written)?;
+    report.write_to(&mut stdout().lock())?;
+
+    let reread = CoberturaReport::from_buf_read(&mut written.as_slice())?;
+    assert_eq!(reread, report);
+    // Branches are kept as the number of covered branches of their line
+    let from_cobertura = CoverageReport::from(&reread);
+    assert_eq!(from_cobertura.branches_found(), coverage.branches_found());
+    assert_eq!(from_cobertura.branches_hit(), coverage.branches_hit());
+
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use std::env;
+use std::fs;
+
+use coverage_formats::format::{detect, open_any};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let paths: Vec<String> = env::args().skip(1).collect();
+    if paths.is_empty() {
+        panic!("usage: coverage_summary <coverage file or GOCOVERDIR>...");
+    }
+
+    for path in paths {
+        if fs::metadata(&path)?.is_file() {
+            let bytes = fs::read(&path)?;
+            match detect(&bytes[..bytes.len().min(1024)]) {
+                Some(detection) => println!(
+                    "{path}: {} ({:?})",
+                    detection.format(),
+                    detection.confidence()
+                ),
+                None => println!("{path}: unknown format"),
+            }
+        } else {
+            println!("{path}: GOCOVERDIR");
+        }
+
+        match open_any(&path) {
+            Ok(coverage) => println!(
+                "  lines {}/{}, branches {}/{}, functions {}/{}",
+                coverage.lines_hit(),
+                coverage.lines_found(),
+                coverage.branches_hit(),
+                coverage.branches_found(),
+                coverage.functions_hit(),
+                coverage.functions_found()
+            ),
+            Err(e) => println!("  {e}"),
+        }
+    }
+
+    Ok(())
+}
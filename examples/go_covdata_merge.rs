@@ -0,0 +1,29 @@
+use std::env;
+
+use coverage_formats::go::GoReport;
+use coverage_formats::go::covdata::CovData;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let output_dir = args
+        .pop()
+        .expect("usage: go_covdata_merge <input_dir>... <output_dir>");
+    if args.is_empty() {
+        return Err("usage: go_covdata_merge <input_dir>... <output_dir>".into());
+    }
+
+    let inputs = args
+        .iter()
+        .map(CovData::from_dir)
+        .collect::<Result<Vec<_>, _>>()?;
+    let merged = CovData::try_merge_all(inputs)?;
+    merged.write_to_dir(&output_dir)?;
+
+    // Merging must not change the coverage
+    let before = GoReport::from_covdata(merged.meta_files(), merged.counter_files())?;
+    let after = GoReport::from_covdata_dir(&output_dir)?;
+    assert_eq!(before, after);
+
+    println!("Merged coverage data written to {output_dir}");
+    Ok(())
+}
@@ -0,0 +1,14 @@
+use std::env;
+
+use coverage_formats::go::GoReport;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let covdata_dir = env::args()
+        .nth(1)
+        .expect("usage: go_covdata_textfmt <gocoverdir>");
+
+    // Same output as `go tool covdata textfmt -i <gocoverdir> -o /dev/stdout`
+    print!("{}", GoReport::from_covdata_dir(covdata_dir)?);
+
+    Ok(())
+}
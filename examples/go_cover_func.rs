@@ -0,0 +1,24 @@
+use std::env;
+use std::fs::File;
+use std::io::BufReader;
+
+use coverage_formats::go::GoReport;
+use coverage_formats::go::source::ModuleRootSource;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = env::args().skip(1);
+    let profile = args
+        .next()
+        .expect("usage: go_cover_func <coverprofile> <module_root>");
+    let module_root = args
+        .next()
+        .expect("usage: go_cover_func <coverprofile> <module_root>");
+
+    let report = GoReport::from_buf_read(&mut BufReader::new(File::open(profile)?))?;
+    let sources = ModuleRootSource::from_go_mod(module_root)?;
+
+    // Same output as `go tool cover -func <coverprofile>`
+    print!("{}", report.func_coverage(&sources)?);
+
+    Ok(())
+}
@@ -0,0 +1,23 @@
+use std::env;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+
+use coverage_formats::go::GoReport;
+use coverage_formats::go::source::ModuleRootSource;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = env::args().skip(1);
+    let usage = "usage: go_cover_html <coverprofile> <module_root> <output_html>";
+    let profile = args.next().expect(usage);
+    let module_root = args.next().expect(usage);
+    let output = args.next().expect(usage);
+
+    let report = GoReport::from_buf_read(&mut BufReader::new(File::open(profile)?))?;
+    let sources = ModuleRootSource::from_go_mod(module_root)?;
+
+    // Same page as `go tool cover -html <coverprofile> -o <output_html>`
+    report.write_html(&sources, &mut BufWriter::new(File::create(&output)?))?;
+
+    println!("HTML report written to {output}");
+    Ok(())
+}
@@ -0,0 +1,31 @@
+use std::env;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+
+use coverage_formats::go::reader::GoProfileReader;
+use coverage_formats::go::writer::GoProfileWriter;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = env::args().skip(1);
+    let usage = "usage: go_filter_profile <coverprofile> <output_file> <filename_prefix>";
+    let profile = args.next().expect(usage);
+    let output_file = args.next().expect(usage);
+    let prefix = args.next().expect(usage);
+
+    // Only one block is held in memory at a time
+    let reader = GoProfileReader::new(BufReader::new(File::open(profile)?))?;
+    let mut writer =
+        GoProfileWriter::new(BufWriter::new(File::create(&output_file)?), reader.mode())?;
+    let mut kept = 0;
+    for block in reader {
+        let block = block?;
+        if block.filename().starts_with(&prefix) {
+            writer.write_block(&block)?;
+            kept += 1;
+        }
+    }
+    writer.finish()?;
+
+    println!("{kept} blocks written to {output_file}");
+    Ok(())
+}
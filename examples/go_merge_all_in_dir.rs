@@ -1,9 +1,11 @@
 use std::env;
 use std::fs::{self, File};
-use std::io::{BufReader, Read, Write};
-use std::path::Path;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::thread;
 
-use coverage_formats::go::GoReport;
+use coverage_formats::Error;
+use coverage_formats::go::{GoCoverageError, GoModeMergePolicy, GoReport};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut args = env::args().skip(1);
@@ -19,37 +21,38 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         return Err(format!("input path is not a directory: {}", input_dir.display()).into());
     }
 
-    let mut merged: Option<GoReport> = None;
-
+    let mut paths = vec![];
     for entry in fs::read_dir(input_dir)? {
-        let entry = entry?;
-        let path = entry.path();
-        if !path.is_file() {
-            continue;
-        }
-
-        let mut file = File::open(&path)?;
-        let mut contents = String::new();
-        file.read_to_string(&mut contents)?;
-
-        if !contents.is_empty() {
-            let report = GoReport::from_buf_read(&mut BufReader::new(&mut contents.as_bytes()))?;
-            merged = match merged {
-                None => Some(report),
-                Some(prev) => Some(prev.try_merge(report)?),
-            };
+        let path = entry?.path();
+        if path.is_file() && fs::metadata(&path)?.len() > 0 {
+            paths.push(path);
         }
     }
+    if paths.is_empty() {
+        return Err("no coverage files found".into());
+    }
 
-    let merged = match merged {
-        Some(r) => r,
-        None => return Err("no coverage files found".into()),
-    };
+    // Files are opened and parsed by the merging threads
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let readers = paths
+        .iter()
+        .map(|path| Ok(BufReader::new(File::open(path)?)));
+    let merged = GoReport::try_merge_all_readers(readers, GoModeMergePolicy::Error, threads)
+        .map_err(|e| format!("{e} ({})", input_name(&e, &paths)))?;
 
-    // Serialize merged report back to text (implement format_write)
-    let mut out = File::create(&output_file)?;
-    out.write_all(format!("{merged}").as_bytes())?;
+    // Profiles of different builds may contain overlapping blocks
+    let merged = merged.try_normalize()?;
+
+    let mut out = BufWriter::new(File::create(&output_file)?);
+    merged.write_to(&mut out)?;
 
     println!("Merged coverage written to {output_file}");
     Ok(())
 }
+
+fn input_name(e: &Error, paths: &[PathBuf]) -> String {
+    match e {
+        Error::Go(GoCoverageError::MergeInput { index, .. }) => paths[*index].display().to_string(),
+        _ => "unknown input".to_string(),
+    }
+}
@@ -0,0 +1,27 @@
+use std::env;
+use std::fs::File;
+use std::io::BufReader;
+
+use coverage_formats::go::GoReport;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let profile = env::args()
+        .nth(1)
+        .expect("usage: go_parse_lenient <coverprofile>");
+
+    let (report, diagnostics) =
+        GoReport::from_buf_read_lenient(&mut BufReader::new(File::open(&profile)?))?;
+    for diagnostic in &diagnostics {
+        eprintln!("{profile}:{diagnostic}");
+    }
+    println!("{} blocks", report.profile_blocks().len());
+
+    // The strict reader fails on the first of them
+    let strict = GoReport::from_buf_read(&mut BufReader::new(File::open(&profile)?));
+    assert_eq!(strict.is_err(), !diagnostics.is_empty());
+    if let Err(e) = strict {
+        eprintln!("{e}");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,29 @@
+use std::env;
+use std::fs::File;
+use std::io::BufReader;
+
+use coverage_formats::go::GoReport;
+use coverage_formats::go::resolver::ModuleResolver;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = env::args().skip(1);
+    let profile = args
+        .next()
+        .expect("usage: go_resolve_paths <coverprofile> <module_or_workspace_dir>");
+    let dir = args
+        .next()
+        .expect("usage: go_resolve_paths <coverprofile> <module_or_workspace_dir>");
+
+    let report = GoReport::from_buf_read(&mut BufReader::new(File::open(profile)?))?;
+    let resolver = ModuleResolver::from_dir(dir)?;
+
+    let local = report.clone().with_local_filenames(&resolver)?;
+    for block in local.profile_blocks() {
+        println!("{}", block.filename());
+    }
+
+    let imported = local.with_import_filenames(&resolver)?;
+    assert_eq!(report, imported);
+
+    Ok(())
+}
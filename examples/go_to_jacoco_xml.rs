@@ -0,0 +1,33 @@
+use std::env;
+use std::fs::File;
+use std::io::{BufReader, stdout};
+
+use coverage_formats::coverage::CoverageReport;
+use coverage_formats::go::{GoModeMergePolicy, GoReport};
+use coverage_formats::jacoco::XmlReport;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let profile = env::args()
+        .nth(1)
+        .expect("usage: go_to_jacoco_xml <coverprofile>");
+
+    let report = GoReport::from_buf_read(&mut BufReader::new(File::open(profile)?))?;
+    let coverage = CoverageReport::from(&report);
+
+    let xml_report = XmlReport::from_coverage(&coverage);
+    xml_report.write_to(&mut stdout().lock())?;
+
+    // Go blocks are kept as regions of the neutral model, with blocks of the same position merged
+    let merged = GoReport::try_merge_all([report.clone()], GoModeMergePolicy::Error)?;
+    assert_eq!(
+        GoReport::from_coverage(&coverage, report.mode().clone()),
+        merged
+    );
+
+    // JaCoCo only keeps whether a line was executed
+    let from_xml = CoverageReport::from(&xml_report);
+    assert_eq!(from_xml.lines_found(), coverage.lines_found());
+    assert_eq!(from_xml.lines_hit(), coverage.lines_hit());
+
+    Ok(())
+}
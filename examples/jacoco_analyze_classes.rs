@@ -0,0 +1,39 @@
+use std::env;
+use std::fs::File;
+use std::io::BufReader;
+
+use coverage_formats::jacoco::{CounterEntity, JacocoReport};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = env::args().skip(1);
+    let exec_file = args
+        .next()
+        .expect("usage: jacoco_analyze_classes <exec_file> <classes_dir_or_jar>");
+    let classes = args
+        .next()
+        .expect("usage: jacoco_analyze_classes <exec_file> <classes_dir_or_jar>");
+
+    let report = JacocoReport::from_read(&mut BufReader::new(File::open(exec_file)?))?;
+
+    let (classes, skipped) = report.analyze_path_with_skipped(classes)?;
+    for e in skipped {
+        eprintln!("Skipped: {e}");
+    }
+
+    println!("CLASS  INSTRUCTIONS  BRANCHES  LINES");
+    for class in classes {
+        let counters = class.counters();
+        let [instructions, branches, lines] = [
+            CounterEntity::Instruction,
+            CounterEntity::Branch,
+            CounterEntity::Line,
+        ]
+        .map(|entity| {
+            let counter = counters.get(entity);
+            format!("{}/{}", counter.covered(), counter.total())
+        });
+        println!("{}  {instructions}  {branches}  {lines}", class.name());
+    }
+
+    Ok(())
+}
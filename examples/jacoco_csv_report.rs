@@ -0,0 +1,30 @@
+use std::env;
+use std::fs::File;
+use std::io::{BufReader, stdout};
+
+use coverage_formats::jacoco::{CsvReport, JacocoReport};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = env::args().skip(1);
+    let exec_file = args
+        .next()
+        .expect("usage: jacoco_csv_report <exec_file> <classes_dir_or_jar>");
+    let classes = args
+        .next()
+        .expect("usage: jacoco_csv_report <exec_file> <classes_dir_or_jar>");
+
+    let report = JacocoReport::from_read(&mut BufReader::new(File::open(exec_file)?))?;
+    let classes = report.analyze_path(classes)?;
+
+    let csv_report = CsvReport::from_classes("jacoco_csv_report", &classes);
+    csv_report.write_to(&mut stdout().lock())?;
+
+    // The CLASS counter isn't part of the CSV report, so compare the written output instead
+    let mut csv = Vec::new();
+    csv_report.write_to(&mut csv)?;
+    let mut rewritten = Vec::new();
+    CsvReport::from_buf_read(&mut csv.as_slice())?.write_to(&mut rewritten)?;
+    assert_eq!(csv, rewritten);
+
+    Ok(())
+}
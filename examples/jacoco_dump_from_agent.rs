@@ -0,0 +1,57 @@
+use std::env;
+use std::io;
+use std::net::{Shutdown, TcpListener};
+use std::thread;
+
+use coverage_formats::jacoco::{
+    ExecutionDataReader, ExecutionDataServer, JacocoReport, RemoteConnection, RemoteSignal,
+};
+
+/// Requests a dump from a JaCoCo agent started with `output=tcpserver`. Without arguments a
+/// loopback stand-in agent replaying `files/kafka_coverage.exec` is started.
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let recorded = std::include_bytes!("./files/kafka_coverage.exec");
+
+    let addr = match env::args().nth(1) {
+        Some(addr) => addr,
+        None => {
+            let listener = TcpListener::bind("127.0.0.1:0")?;
+            let addr = listener.local_addr()?.to_string();
+            thread::spawn(move || -> coverage_formats::Result<()> {
+                let (stream, _) = listener.accept()?;
+                let mut agent = RemoteConnection::new(stream)?;
+                let mut ignored = JacocoReport::new(vec![], vec![]);
+                while let RemoteSignal::DumpCommand { dump, .. } = agent.read(&mut ignored)? {
+                    if dump {
+                        ExecutionDataReader::new(&recorded[..]).read(&mut agent)?;
+                    }
+                    agent.send_cmd_ok()?;
+                }
+                Ok(())
+            });
+            addr
+        }
+    };
+
+    let report = JacocoReport::dump_from_agent(&addr, false)?;
+    eprintln!("{report}");
+
+    // The same protocol is used by agents started with `output=tcpclient`, which connect to us
+    let server = ExecutionDataServer::bind("127.0.0.1:0")?;
+    let server_addr = server.local_addr()?;
+    thread::spawn(move || -> coverage_formats::Result<()> {
+        let mut agent = RemoteConnection::connect(server_addr)?;
+        ExecutionDataReader::new(&recorded[..]).read(&mut agent)?;
+        agent.send_cmd_ok()?;
+        // Like an agent's reader loop, consume the server's header before closing, otherwise
+        // the unread data turns the close into a connection reset
+        let mut stream = agent.into_inner();
+        stream.shutdown(Shutdown::Write)?;
+        io::copy(&mut stream, &mut io::sink())?;
+        Ok(())
+    });
+    let collected = server.collect_next()?;
+    assert_eq!(collected.execution_datas(), report.execution_datas());
+
+    Ok(())
+}
@@ -0,0 +1,16 @@
+use coverage_formats::jacoco::JacocoReport;
+
+fn main() {
+    let r = std::include_bytes!("./files/kafka_coverage.exec");
+    let decoded = JacocoReport::from_read(&mut &r[..]).unwrap();
+
+    let mut encoded = Vec::with_capacity(r.len());
+    decoded.write_to(&mut encoded).unwrap();
+
+    assert_eq!(
+        &r[..],
+        &encoded[..],
+        "round-trip must be byte-for-byte identical"
+    );
+    eprintln!("Round-tripped {} bytes", encoded.len());
+}
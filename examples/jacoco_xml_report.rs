@@ -0,0 +1,32 @@
+use std::env;
+use std::fs::File;
+use std::io::{BufReader, stdout};
+
+use coverage_formats::jacoco::{JacocoReport, XmlReport};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = env::args().skip(1);
+    let exec_file = args
+        .next()
+        .expect("usage: jacoco_xml_report <exec_file> <classes_dir_or_jar>");
+    let classes = args
+        .next()
+        .expect("usage: jacoco_xml_report <exec_file> <classes_dir_or_jar>");
+
+    let report = JacocoReport::from_read(&mut BufReader::new(File::open(exec_file)?))?;
+    let classes = report.analyze_path(classes)?;
+
+    let xml_report = XmlReport::from_classes(
+        "jacoco_xml_report".to_string(),
+        report.session_infos().clone(),
+        &classes,
+    );
+    xml_report.write_to(&mut stdout().lock())?;
+
+    // The written report can be read back into the same model
+    let mut xml = Vec::new();
+    xml_report.write_to(&mut xml)?;
+    assert_eq!(XmlReport::from_buf_read(&mut xml.as_slice())?, xml_report);
+
+    Ok(())
+}
@@ -0,0 +1,29 @@
+use std::env;
+use std::fs::File;
+use std::io::{BufReader, stdout};
+
+use coverage_formats::lcov::LcovReport;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let paths: Vec<String> = env::args().skip(1).collect();
+    if paths.is_empty() {
+        panic!("usage: lcov_merge <tracefile>...");
+    }
+
+    let mut reports = vec![];
+    for path in &paths {
+        let report = LcovReport::from_buf_read(&mut BufReader::new(File::open(path)?))?;
+        reports.push(report);
+    }
+
+    // Same as `lcov -a <tracefile> -a <tracefile>...`
+    let merged = LcovReport::try_merge_all(reports)?;
+    let mut written = vec![];
+    merged.write_to(&mut written)?;
+    merged.write_to(&mut stdout().lock())?;
+
+    let reread = LcovReport::from_buf_read(&mut written.as_slice())?;
+    assert_eq!(reread, merged);
+
+    Ok(())
+}
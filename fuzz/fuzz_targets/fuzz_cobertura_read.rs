@@ -0,0 +1,9 @@
+#![no_main]
+
+use coverage_formats::cobertura::CoberturaReport;
+use coverage_formats_fuzz::read_write;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    read_write::<CoberturaReport>(data);
+});
@@ -0,0 +1,10 @@
+#![no_main]
+
+use coverage_formats::go::covdata::{CounterFile, MetaFile};
+use coverage_formats_fuzz::read_write;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    read_write::<MetaFile>(data);
+    read_write::<CounterFile>(data);
+});
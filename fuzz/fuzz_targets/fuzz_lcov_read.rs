@@ -0,0 +1,10 @@
+#![no_main]
+
+use coverage_formats::lcov::LcovReport;
+use coverage_formats_fuzz::{merge, read_write};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    read_write::<LcovReport>(data);
+    merge::<LcovReport>(data);
+});
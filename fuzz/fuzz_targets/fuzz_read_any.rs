@@ -0,0 +1,8 @@
+#![no_main]
+
+use coverage_formats::format::read_any;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = read_any(&mut &data[..]);
+});
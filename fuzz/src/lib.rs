@@ -0,0 +1,34 @@
+//! Harness shared by all fuzz targets, generic over the format.
+
+use coverage_formats::report::{CoverageReader, CoverageWriter, Merge};
+
+use std::fmt::Debug;
+
+/// Reads `data`, and if it's a valid report, checks that writing it succeeds and that the
+/// written report reads back the same.
+pub fn read_write<T>(mut data: &[u8])
+where
+    T: CoverageReader + CoverageWriter + PartialEq + Debug,
+{
+    let Ok(report) = T::from_buf_read(&mut data) else {
+        return;
+    };
+    let mut written = vec![];
+    report
+        .write_to(&mut written)
+        .expect("a report that was read can be written");
+    let reread = T::from_buf_read(&mut written.as_slice())
+        .expect("a written report can be read again");
+    assert_eq!(report, reread);
+}
+
+/// Splits `data` in two reports and merges them if both are valid.
+pub fn merge<T>(data: &[u8])
+where
+    T: CoverageReader + Merge,
+{
+    let (mut left, mut right) = data.split_at(data.len() / 2);
+    if let (Ok(left), Ok(right)) = (T::from_buf_read(&mut left), T::from_buf_read(&mut right)) {
+        let _ = left.try_merge(right);
+    }
+}
@@ -0,0 +1,135 @@
+//! Conversion between [`CoberturaReport`] and the format-neutral [`CoverageReport`].
+
+use super::{
+    CoberturaClass, CoberturaLine, CoberturaMethod, CoberturaPackage, CoberturaReport,
+    ConditionCoverage,
+};
+use crate::coverage::{BranchCoverage, CoverageReport, FileCoverage, FunctionCoverage};
+
+use std::collections::BTreeMap;
+
+/// Files are identified by the file names of the classes, relative to the sources, which become
+/// the source roots. Classes of the same file are combined, a line gets the highest hit count.
+/// The branches of a line are numbered covered ones first, they are unknown (`None`) if the
+/// line wasn't executed. Methods become functions named `<class>.<method><signature>` with the
+/// highest hit count of their lines.
+impl From<&CoberturaReport> for CoverageReport {
+    fn from(report: &CoberturaReport) -> Self {
+        let mut coverage = CoverageReport::new(None);
+        *coverage.source_roots_mut() = report.sources.clone();
+        for package in &report.packages {
+            for class in &package.classes {
+                let file = coverage.file_mut(&class.filename);
+                if file.package().is_none() {
+                    *file.package_mut() = Some(package.name.clone());
+                }
+                add_class(file, class);
+            }
+        }
+        coverage
+    }
+}
+
+fn add_class(file: &mut FileCoverage, class: &CoberturaClass) {
+    for (&number, line) in &class.lines {
+        let hits = file.lines_mut().entry(number).or_default();
+        *hits = (*hits).max(line.hits);
+
+        let Some(conditions) = line.condition_coverage else {
+            continue;
+        };
+        if file.branches().iter().any(|b| b.line() == number) {
+            continue;
+        }
+        for i in 0..conditions.total {
+            let taken = (line.hits > 0).then_some(u64::from(i < conditions.covered));
+            file.branches_mut()
+                .push(BranchCoverage::new(number, 0, i, taken));
+        }
+    }
+    for method in &class.methods {
+        let hits = method.lines.values().map(|line| line.hits).max();
+        file.functions_mut().push(FunctionCoverage::new(
+            format!("{}.{}{}", class.name, method.name, method.signature),
+            method.lines.keys().next().copied(),
+            method.lines.keys().next_back().copied(),
+            hits.unwrap_or_default(),
+        ));
+    }
+}
+
+impl CoberturaReport {
+    /// Builds a report with a class per file, named after the file, in a package per package
+    /// of the files, or per directory for files without package. Functions become methods
+    /// with the lines from their start to their end line. The source roots become the sources.
+    pub fn from_coverage(coverage: &CoverageReport, version: String, timestamp: u64) -> Self {
+        let mut classes_by_package: BTreeMap<String, Vec<CoberturaClass>> = BTreeMap::new();
+        for (path, file) in coverage.files() {
+            let package = file.package().cloned().unwrap_or_else(|| {
+                path.rsplit_once('/')
+                    .map(|(dir, _)| dir.to_string())
+                    .unwrap_or_default()
+            });
+            classes_by_package
+                .entry(package)
+                .or_default()
+                .push(cobertura_class(path, file));
+        }
+
+        let mut report = CoberturaReport::new(version, timestamp);
+        report.sources = coverage.source_roots().clone();
+        report.packages = classes_by_package
+            .into_iter()
+            .map(|(name, classes)| CoberturaPackage::new(name, classes))
+            .collect();
+        report
+    }
+}
+
+fn cobertura_class(path: &str, file: &FileCoverage) -> CoberturaClass {
+    let mut class = CoberturaClass::new(path.to_string(), path.to_string());
+
+    let mut branches: BTreeMap<u32, ConditionCoverage> = BTreeMap::new();
+    // Executions of a block are the sum of its branches
+    let mut block_hits: BTreeMap<(u32, u32), u64> = BTreeMap::new();
+    for branch in file.branches() {
+        let conditions = branches.entry(branch.line()).or_default();
+        conditions.total += 1;
+        let taken = branch.taken().unwrap_or_default();
+        if taken > 0 {
+            conditions.covered += 1;
+        }
+        let hits = block_hits
+            .entry((branch.line(), branch.block()))
+            .or_default();
+        *hits = hits.saturating_add(taken);
+    }
+    for (&number, &hits) in file.lines() {
+        let line = CoberturaLine::new(hits, branches.get(&number).copied());
+        class.lines.insert(number, line);
+    }
+    // Branches are part of a line in Cobertura, lines only known from their branches are added
+    for ((number, _), hits) in block_hits {
+        let line = class
+            .lines
+            .entry(number)
+            .or_insert_with(|| CoberturaLine::new(0, branches.get(&number).copied()));
+        if !file.lines().contains_key(&number) {
+            line.hits = line.hits.max(hits);
+        }
+    }
+
+    for function in file.functions() {
+        let mut method = CoberturaMethod::new(function.name().clone(), String::new());
+        if let Some(start) = function.start_line() {
+            let end = function.end_line().unwrap_or(start).max(start);
+            method.lines = class
+                .lines
+                .range(start..=end)
+                .map(|(&number, line)| (number, line.clone()))
+                .collect();
+        }
+        class.methods.push(method);
+    }
+    class
+}
@@ -0,0 +1,10 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum CoberturaError {
+    #[error("Invalid Cobertura report: missing or invalid attribute {attribute:?} of <{element}>")]
+    InvalidXmlAttribute { element: String, attribute: String },
+
+    #[error("Invalid Cobertura report: no <coverage> element")]
+    MissingCoverageElement,
+}
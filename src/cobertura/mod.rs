@@ -0,0 +1,406 @@
+//! Cobertura XML reports, following Cobertura's `coverage-04.dtd`, as written by coverage.py,
+//! gcovr and many other tools and shown by GitLab and Azure DevOps.
+
+use std::collections::BTreeMap;
+
+pub(super) use super::Result;
+pub mod error;
+pub use error::CoberturaError;
+
+pub mod convert;
+pub mod reader;
+pub mod writer;
+
+#[cfg(feature = "serialization")]
+use serde::{Deserialize, Serialize};
+
+/// Rates and complexity aren't kept, rates are computed from the lines when writing, see
+/// [`CoberturaSummary`].
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct CoberturaReport {
+    /// Version of the tool that wrote the report
+    version: String,
+    /// Milliseconds since the epoch, seconds for some tools
+    timestamp: u64,
+    /// Directories the file names of classes are relative to
+    sources: Vec<String>,
+    packages: Vec<CoberturaPackage>,
+}
+
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct CoberturaPackage {
+    name: String,
+    classes: Vec<CoberturaClass>,
+}
+
+/// A class, or a source file for languages without classes.
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct CoberturaClass {
+    name: String,
+    filename: String,
+    methods: Vec<CoberturaMethod>,
+    /// All lines of the class, including the ones of its methods
+    lines: BTreeMap<u32, CoberturaLine>,
+}
+
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct CoberturaMethod {
+    name: String,
+    signature: String,
+    lines: BTreeMap<u32, CoberturaLine>,
+}
+
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct CoberturaLine {
+    hits: u64,
+    /// `condition-coverage` of a line with `branch="true"`
+    condition_coverage: Option<ConditionCoverage>,
+    conditions: Vec<CoberturaCondition>,
+}
+
+/// Number of covered and total branches of a line, written as `50% (1/2)`.
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct ConditionCoverage {
+    covered: u32,
+    total: u32,
+}
+
+/// A condition of a branch line, only written by Cobertura itself.
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct CoberturaCondition {
+    number: u32,
+    /// `type`, e.g. `jump` or `switch`
+    kind: String,
+    /// Percentage of covered outcomes
+    coverage: u32,
+}
+
+/// Counts behind the `line-rate` and `branch-rate` attributes.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct CoberturaSummary {
+    lines_valid: usize,
+    lines_covered: usize,
+    branches_valid: u64,
+    branches_covered: u64,
+}
+
+impl CoberturaReport {
+    pub fn new(version: String, timestamp: u64) -> Self {
+        Self {
+            version,
+            timestamp,
+            ..Default::default()
+        }
+    }
+
+    pub fn version(&self) -> &String {
+        &self.version
+    }
+
+    pub fn version_mut(&mut self) -> &mut String {
+        &mut self.version
+    }
+
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    pub fn timestamp_mut(&mut self) -> &mut u64 {
+        &mut self.timestamp
+    }
+
+    pub fn sources(&self) -> &Vec<String> {
+        &self.sources
+    }
+
+    pub fn sources_mut(&mut self) -> &mut Vec<String> {
+        &mut self.sources
+    }
+
+    pub fn packages(&self) -> &Vec<CoberturaPackage> {
+        &self.packages
+    }
+
+    pub fn packages_mut(&mut self) -> &mut Vec<CoberturaPackage> {
+        &mut self.packages
+    }
+
+    pub fn summary(&self) -> CoberturaSummary {
+        let mut summary = CoberturaSummary::default();
+        for package in &self.packages {
+            summary.add(package.summary());
+        }
+        summary
+    }
+}
+
+impl CoberturaPackage {
+    pub fn new(name: String, classes: Vec<CoberturaClass>) -> Self {
+        Self { name, classes }
+    }
+
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
+
+    pub fn classes(&self) -> &Vec<CoberturaClass> {
+        &self.classes
+    }
+
+    pub fn classes_mut(&mut self) -> &mut Vec<CoberturaClass> {
+        &mut self.classes
+    }
+
+    pub fn summary(&self) -> CoberturaSummary {
+        let mut summary = CoberturaSummary::default();
+        for class in &self.classes {
+            summary.add(class.summary());
+        }
+        summary
+    }
+}
+
+impl CoberturaClass {
+    pub fn new(name: String, filename: String) -> Self {
+        Self {
+            name,
+            filename,
+            ..Default::default()
+        }
+    }
+
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
+
+    pub fn filename(&self) -> &String {
+        &self.filename
+    }
+
+    pub fn filename_mut(&mut self) -> &mut String {
+        &mut self.filename
+    }
+
+    pub fn methods(&self) -> &Vec<CoberturaMethod> {
+        &self.methods
+    }
+
+    pub fn methods_mut(&mut self) -> &mut Vec<CoberturaMethod> {
+        &mut self.methods
+    }
+
+    pub fn lines(&self) -> &BTreeMap<u32, CoberturaLine> {
+        &self.lines
+    }
+
+    pub fn lines_mut(&mut self) -> &mut BTreeMap<u32, CoberturaLine> {
+        &mut self.lines
+    }
+
+    pub fn summary(&self) -> CoberturaSummary {
+        CoberturaSummary::of_lines(&self.lines)
+    }
+}
+
+impl CoberturaMethod {
+    pub fn new(name: String, signature: String) -> Self {
+        Self {
+            name,
+            signature,
+            ..Default::default()
+        }
+    }
+
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
+
+    pub fn signature(&self) -> &String {
+        &self.signature
+    }
+
+    pub fn signature_mut(&mut self) -> &mut String {
+        &mut self.signature
+    }
+
+    pub fn lines(&self) -> &BTreeMap<u32, CoberturaLine> {
+        &self.lines
+    }
+
+    pub fn lines_mut(&mut self) -> &mut BTreeMap<u32, CoberturaLine> {
+        &mut self.lines
+    }
+
+    pub fn summary(&self) -> CoberturaSummary {
+        CoberturaSummary::of_lines(&self.lines)
+    }
+}
+
+impl CoberturaLine {
+    pub fn new(hits: u64, condition_coverage: Option<ConditionCoverage>) -> Self {
+        Self {
+            hits,
+            condition_coverage,
+            conditions: vec![],
+        }
+    }
+
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    pub fn hits_mut(&mut self) -> &mut u64 {
+        &mut self.hits
+    }
+
+    pub fn condition_coverage(&self) -> Option<ConditionCoverage> {
+        self.condition_coverage
+    }
+
+    pub fn condition_coverage_mut(&mut self) -> &mut Option<ConditionCoverage> {
+        &mut self.condition_coverage
+    }
+
+    pub fn conditions(&self) -> &Vec<CoberturaCondition> {
+        &self.conditions
+    }
+
+    pub fn conditions_mut(&mut self) -> &mut Vec<CoberturaCondition> {
+        &mut self.conditions
+    }
+}
+
+impl ConditionCoverage {
+    pub fn new(covered: u32, total: u32) -> Self {
+        Self { covered, total }
+    }
+
+    pub fn covered(&self) -> u32 {
+        self.covered
+    }
+
+    pub fn total(&self) -> u32 {
+        self.total
+    }
+
+    /// Rounded down like Cobertura does, 100 if there are no branches.
+    pub fn percent(&self) -> u64 {
+        if self.total == 0 {
+            return 100;
+        }
+        u64::from(self.covered.min(self.total)) * 100 / u64::from(self.total)
+    }
+}
+
+impl CoberturaCondition {
+    pub fn new(number: u32, kind: String, coverage: u32) -> Self {
+        Self {
+            number,
+            kind,
+            coverage,
+        }
+    }
+
+    pub fn number(&self) -> u32 {
+        self.number
+    }
+
+    pub fn number_mut(&mut self) -> &mut u32 {
+        &mut self.number
+    }
+
+    pub fn kind(&self) -> &String {
+        &self.kind
+    }
+
+    pub fn kind_mut(&mut self) -> &mut String {
+        &mut self.kind
+    }
+
+    pub fn coverage(&self) -> u32 {
+        self.coverage
+    }
+
+    pub fn coverage_mut(&mut self) -> &mut u32 {
+        &mut self.coverage
+    }
+}
+
+impl CoberturaSummary {
+    pub fn lines_valid(&self) -> usize {
+        self.lines_valid
+    }
+
+    pub fn lines_covered(&self) -> usize {
+        self.lines_covered
+    }
+
+    pub fn branches_valid(&self) -> u64 {
+        self.branches_valid
+    }
+
+    pub fn branches_covered(&self) -> u64 {
+        self.branches_covered
+    }
+
+    /// Share of lines with hits, 1 if there are no lines.
+    pub fn line_rate(&self) -> f64 {
+        rate(self.lines_covered as u64, self.lines_valid as u64)
+    }
+
+    /// Share of covered branches, 1 if there are no branches.
+    pub fn branch_rate(&self) -> f64 {
+        rate(self.branches_covered, self.branches_valid)
+    }
+
+    fn of_lines(lines: &BTreeMap<u32, CoberturaLine>) -> Self {
+        let mut summary = Self {
+            lines_valid: lines.len(),
+            ..Default::default()
+        };
+        for line in lines.values() {
+            if line.hits > 0 {
+                summary.lines_covered += 1;
+            }
+            if let Some(conditions) = line.condition_coverage {
+                summary.branches_valid += u64::from(conditions.total);
+                summary.branches_covered += u64::from(conditions.covered.min(conditions.total));
+            }
+        }
+        summary
+    }
+
+    fn add(&mut self, other: Self) {
+        self.lines_valid += other.lines_valid;
+        self.lines_covered += other.lines_covered;
+        self.branches_valid += other.branches_valid;
+        self.branches_covered += other.branches_covered;
+    }
+}
+
+fn rate(covered: u64, valid: u64) -> f64 {
+    if valid == 0 {
+        1.0
+    } else {
+        covered as f64 / valid as f64
+    }
+}
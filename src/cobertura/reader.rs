@@ -0,0 +1,215 @@
+use super::Result;
+use super::{
+    CoberturaClass, CoberturaCondition, CoberturaError, CoberturaLine, CoberturaMethod,
+    CoberturaPackage, CoberturaReport, ConditionCoverage,
+};
+use crate::xml::{XmlElement, XmlElementReader};
+
+use std::collections::BTreeMap;
+use std::io::BufRead;
+use std::str::FromStr;
+
+impl CoberturaReport {
+    /// Reads a report written by Cobertura, coverage.py, gcovr or any other tool following
+    /// `coverage-04.dtd`. The wrapper elements `sources`, `packages`, `classes`, `methods`,
+    /// `lines` and `conditions` may be left out, rates, complexity and unknown elements are
+    /// ignored.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `CoberturaError::MissingCoverageElement` if there is no `<coverage>` element,
+    /// with `CoberturaError::InvalidXmlAttribute` if a required attribute is missing or a
+    /// number is invalid, and with the XML or IO error if the document isn't well-formed
+    pub fn from_buf_read<R: BufRead>(r: &mut R) -> Result<Self> {
+        let mut reader = XmlElementReader::new(r);
+        while let Some(element) = reader.next_element()? {
+            if element.name == "coverage" {
+                return read_coverage(&mut reader, &element);
+            }
+            reader.skip(&element)?;
+        }
+        Err(CoberturaError::MissingCoverageElement.into())
+    }
+}
+
+fn read_coverage<R: BufRead>(
+    reader: &mut XmlElementReader<R>,
+    element: &XmlElement,
+) -> Result<CoberturaReport> {
+    // Milliseconds for Cobertura and coverage.py, seconds for gcovr, fractional for some tools
+    let timestamp = match element.attribute("timestamp") {
+        Some(value) => value
+            .parse::<u64>()
+            .ok()
+            .or_else(|| {
+                value
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|t| *t >= 0.0)
+                    .map(|t| t as u64)
+            })
+            .ok_or_else(|| invalid_attribute(element, "timestamp"))?,
+        None => 0,
+    };
+    let version = element.attribute("version").unwrap_or_default().to_string();
+    let mut report = CoberturaReport::new(version, timestamp);
+    read_children(
+        reader,
+        element,
+        &["sources", "packages"],
+        &mut |reader, child| {
+            match child.name.as_str() {
+                "source" => report.sources.push(reader.read_text(child)?),
+                "package" => report.packages.push(read_package(reader, child)?),
+                _ => return Ok(false),
+            }
+            Ok(true)
+        },
+    )?;
+    Ok(report)
+}
+
+fn read_package<R: BufRead>(
+    reader: &mut XmlElementReader<R>,
+    element: &XmlElement,
+) -> Result<CoberturaPackage> {
+    let mut package = CoberturaPackage::new(required(element, "name")?.to_string(), vec![]);
+    read_children(reader, element, &["classes"], &mut |reader, child| {
+        if child.name != "class" {
+            return Ok(false);
+        }
+        package.classes.push(read_class(reader, child)?);
+        Ok(true)
+    })?;
+    Ok(package)
+}
+
+fn read_class<R: BufRead>(
+    reader: &mut XmlElementReader<R>,
+    element: &XmlElement,
+) -> Result<CoberturaClass> {
+    let mut class = CoberturaClass::new(
+        required(element, "name")?.to_string(),
+        required(element, "filename")?.to_string(),
+    );
+    read_children(
+        reader,
+        element,
+        &["methods", "lines"],
+        &mut |reader, child| {
+            match child.name.as_str() {
+                "method" => {
+                    let mut method = CoberturaMethod::new(
+                        required(child, "name")?.to_string(),
+                        child.attribute("signature").unwrap_or_default().to_string(),
+                    );
+                    read_children(reader, child, &["lines"], &mut |reader, line| {
+                        read_line(reader, line, &mut method.lines)
+                    })?;
+                    class.methods.push(method);
+                }
+                "line" => {
+                    read_line(reader, child, &mut class.lines)?;
+                }
+                _ => return Ok(false),
+            }
+            Ok(true)
+        },
+    )?;
+    Ok(class)
+}
+
+/// Reads `element` into `lines` if it's a `<line>`, the last of several lines with the same
+/// number wins.
+fn read_line<R: BufRead>(
+    reader: &mut XmlElementReader<R>,
+    element: &XmlElement,
+    lines: &mut BTreeMap<u32, CoberturaLine>,
+) -> Result<bool> {
+    if element.name != "line" {
+        return Ok(false);
+    }
+    let is_branch = element
+        .attribute("branch")
+        .is_some_and(|branch| branch.eq_ignore_ascii_case("true"));
+    let condition_coverage = match element.attribute("condition-coverage") {
+        Some(value) if is_branch => parse_condition_coverage(value),
+        _ => None,
+    };
+    let mut line = CoberturaLine::new(parse_required(element, "hits")?, condition_coverage);
+    read_children(reader, element, &["conditions"], &mut |_, child| {
+        if child.name != "condition" {
+            return Ok(false);
+        }
+        let coverage = required(child, "coverage")?;
+        let coverage = coverage
+            .trim_end_matches('%')
+            .trim()
+            .parse()
+            .map_err(|_| invalid_attribute(child, "coverage"))?;
+        line.conditions.push(CoberturaCondition::new(
+            parse_required(child, "number")?,
+            child.attribute("type").unwrap_or_default().to_string(),
+            coverage,
+        ));
+        Ok(false)
+    })?;
+    lines.insert(parse_required(element, "number")?, line);
+    Ok(true)
+}
+
+/// Covered and total branches of `50% (1/2)`, `None` if only the percentage is given.
+fn parse_condition_coverage(value: &str) -> Option<ConditionCoverage> {
+    let (_, counts) = value.split_once('(')?;
+    let (covered, total) = counts.trim_end().strip_suffix(')')?.split_once('/')?;
+    Some(ConditionCoverage::new(
+        covered.trim().parse().ok()?,
+        total.trim().parse().ok()?,
+    ))
+}
+
+/// Calls `f` for the children of `element`, including the children of `wrappers`. `f`
+/// returns whether it consumed the child, other children are skipped.
+fn read_children<R, F>(
+    reader: &mut XmlElementReader<R>,
+    element: &XmlElement,
+    wrappers: &[&str],
+    f: &mut F,
+) -> Result<()>
+where
+    R: BufRead,
+    F: FnMut(&mut XmlElementReader<R>, &XmlElement) -> Result<bool>,
+{
+    if element.is_empty {
+        return Ok(());
+    }
+    while let Some(child) = reader.next_element()? {
+        if wrappers.contains(&child.name.as_str()) {
+            read_children(reader, &child, wrappers, f)?;
+        } else if !f(reader, &child)? {
+            reader.skip(&child)?;
+        }
+    }
+    Ok(())
+}
+
+fn required<'a>(element: &'a XmlElement, attribute: &str) -> Result<&'a str> {
+    element
+        .attribute(attribute)
+        .ok_or_else(|| invalid_attribute(element, attribute))
+}
+
+fn parse_required<T: FromStr>(element: &XmlElement, attribute: &str) -> Result<T> {
+    required(element, attribute)?
+        .trim()
+        .parse()
+        .map_err(|_| invalid_attribute(element, attribute))
+}
+
+fn invalid_attribute(element: &XmlElement, attribute: &str) -> crate::Error {
+    CoberturaError::InvalidXmlAttribute {
+        element: element.name.clone(),
+        attribute: attribute.to_string(),
+    }
+    .into()
+}
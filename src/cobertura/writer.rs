@@ -0,0 +1,178 @@
+use super::Result;
+use super::{CoberturaClass, CoberturaLine, CoberturaPackage, CoberturaReport, CoberturaSummary};
+use crate::xml::escape;
+
+use std::collections::BTreeMap;
+use std::io::Write;
+
+impl CoberturaReport {
+    /// Writes the report valid against `coverage-04.dtd`, indented like the reports of gcovr.
+    /// Rates and counts are computed from the lines, complexity isn't known and written as 0.
+    ///
+    /// # Errors
+    ///
+    /// Will return the error of `w` if writing fails
+    pub fn write_to<W: Write>(&self, w: &mut W) -> Result<()> {
+        writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            w,
+            r#"<!DOCTYPE coverage SYSTEM "http://cobertura.sourceforge.net/xml/coverage-04.dtd">"#
+        )?;
+        let summary = self.summary();
+        writeln!(
+            w,
+            r#"<coverage line-rate="{}" branch-rate="{}" lines-covered="{}" lines-valid="{}" branches-covered="{}" branches-valid="{}" complexity="0" version="{}" timestamp="{}">"#,
+            format_rate(summary.line_rate()),
+            format_rate(summary.branch_rate()),
+            summary.lines_covered(),
+            summary.lines_valid(),
+            summary.branches_covered(),
+            summary.branches_valid(),
+            escape(&self.version),
+            self.timestamp
+        )?;
+        writeln!(w, "  <sources>")?;
+        for source in &self.sources {
+            writeln!(w, "    <source>{}</source>", escape(source))?;
+        }
+        writeln!(w, "  </sources>")?;
+        writeln!(w, "  <packages>")?;
+        for package in &self.packages {
+            write_package(w, package)?;
+        }
+        writeln!(w, "  </packages>")?;
+        writeln!(w, "</coverage>")?;
+        Ok(())
+    }
+}
+
+fn write_package<W: Write>(w: &mut W, package: &CoberturaPackage) -> Result<()> {
+    writeln!(
+        w,
+        r#"    <package name="{}" {} complexity="0">"#,
+        escape(&package.name),
+        rates(package.summary())
+    )?;
+    writeln!(w, "      <classes>")?;
+    for class in &package.classes {
+        write_class(w, class)?;
+    }
+    writeln!(w, "      </classes>")?;
+    writeln!(w, "    </package>")?;
+    Ok(())
+}
+
+fn write_class<W: Write>(w: &mut W, class: &CoberturaClass) -> Result<()> {
+    writeln!(
+        w,
+        r#"        <class name="{}" filename="{}" {} complexity="0">"#,
+        escape(&class.name),
+        escape(&class.filename),
+        rates(class.summary())
+    )?;
+    writeln!(w, "          <methods>")?;
+    for method in &class.methods {
+        writeln!(
+            w,
+            r#"            <method name="{}" signature="{}" {} complexity="0">"#,
+            escape(&method.name),
+            escape(&method.signature),
+            rates(method.summary())
+        )?;
+        write_lines(w, &method.lines, "              ")?;
+        writeln!(w, "            </method>")?;
+    }
+    writeln!(w, "          </methods>")?;
+    write_lines(w, &class.lines, "          ")?;
+    writeln!(w, "        </class>")?;
+    Ok(())
+}
+
+fn write_lines<W: Write>(
+    w: &mut W,
+    lines: &BTreeMap<u32, CoberturaLine>,
+    indent: &str,
+) -> Result<()> {
+    writeln!(w, "{indent}<lines>")?;
+    for (number, line) in lines {
+        write!(
+            w,
+            r#"{indent}  <line number="{number}" hits="{}""#,
+            line.hits
+        )?;
+        match line.condition_coverage {
+            Some(conditions) => write!(
+                w,
+                r#" branch="true" condition-coverage="{}% ({}/{})""#,
+                conditions.percent(),
+                conditions.covered,
+                conditions.total
+            )?,
+            None => write!(w, r#" branch="false""#)?,
+        }
+        if line.conditions.is_empty() {
+            writeln!(w, "/>")?;
+            continue;
+        }
+        writeln!(w, ">")?;
+        writeln!(w, "{indent}    <conditions>")?;
+        for condition in &line.conditions {
+            writeln!(
+                w,
+                r#"{indent}      <condition number="{}" type="{}" coverage="{}%"/>"#,
+                condition.number,
+                escape(&condition.kind),
+                condition.coverage
+            )?;
+        }
+        writeln!(w, "{indent}    </conditions>")?;
+        writeln!(w, "{indent}  </line>")?;
+    }
+    writeln!(w, "{indent}</lines>")?;
+    Ok(())
+}
+
+fn rates(summary: CoberturaSummary) -> String {
+    format!(
+        r#"line-rate="{}" branch-rate="{}""#,
+        format_rate(summary.line_rate()),
+        format_rate(summary.branch_rate())
+    )
+}
+
+/// Rounded to 4 decimals like coverage.py, e.g. `0.8333`, `1` or `0`.
+fn format_rate(rate: f64) -> String {
+    let rounded = (rate * 10_000.0).round() / 10_000.0;
+    format!("{rounded}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The writer formats the XML like gcovr, so only the read reports are compared.
+    fn assert_round_trip(bytes: &[u8]) -> CoberturaReport {
+        let report = CoberturaReport::from_buf_read(&mut &bytes[..]).unwrap();
+        let mut written = vec![];
+        report.write_to(&mut written).unwrap();
+        assert_eq!(
+            CoberturaReport::from_buf_read(&mut written.as_slice()).unwrap(),
+            report
+        );
+        report
+    }
+
+    #[test]
+    fn coverage_py_report_round_trip() {
+        let report = assert_round_trip(include_bytes!("../../examples/files/coverage_py.xml"));
+        assert_eq!(report.summary().line_rate(), 0.75);
+        assert_eq!(report.summary().branch_rate(), 0.5);
+    }
+
+    #[test]
+    fn gcovr_report_round_trip() {
+        let report = assert_round_trip(include_bytes!("../../examples/files/gcovr.xml"));
+        assert_eq!(report.summary().line_rate(), 0.5);
+        assert_eq!(report.summary().branch_rate(), 0.25);
+    }
+}
@@ -0,0 +1,464 @@
+//! Format-neutral coverage model. Every format converts into and out of a [`CoverageReport`], so
+//! reports can be converted between any two formats and tools can handle all of them the same
+//! way.
+
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+
+#[cfg(feature = "serialization")]
+use serde::{Deserialize, Serialize};
+
+/// Coverage of source files, keyed by their path.
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct CoverageReport {
+    name: Option<String>,
+    /// Directories the file paths are relative to
+    source_roots: Vec<String>,
+    files: BTreeMap<String, FileCoverage>,
+}
+
+/// Coverage of a single source file. Formats differ in what they record, so any part of it may
+/// be empty.
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct FileCoverage {
+    /// Package, or import path of the package, the file belongs to
+    package: Option<String>,
+    /// Hit count of every line with executable code
+    lines: BTreeMap<u32, u64>,
+    branches: Vec<BranchCoverage>,
+    functions: Vec<FunctionCoverage>,
+    /// Source ranges with exact positions, e.g. the blocks of Go profiles
+    regions: Vec<RegionCoverage>,
+}
+
+/// A branch identified by its line, the block of branches on that line and its index in the
+/// block, like LCOV's `BRDA` records.
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct BranchCoverage {
+    line: u32,
+    block: u32,
+    branch: u32,
+    /// `None` if the code containing the branch was never executed
+    taken: Option<u64>,
+}
+
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct FunctionCoverage {
+    name: String,
+    start_line: Option<u32>,
+    end_line: Option<u32>,
+    hits: u64,
+}
+
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct RegionCoverage {
+    start_line: u32,
+    start_col: u32,
+    end_line: u32,
+    end_col: u32,
+    number_of_statements: u32,
+    hits: u64,
+}
+
+impl CoverageReport {
+    pub fn new(name: Option<String>) -> Self {
+        Self {
+            name,
+            ..Default::default()
+        }
+    }
+
+    pub fn name(&self) -> Option<&String> {
+        self.name.as_ref()
+    }
+
+    pub fn name_mut(&mut self) -> &mut Option<String> {
+        &mut self.name
+    }
+
+    pub fn source_roots(&self) -> &Vec<String> {
+        &self.source_roots
+    }
+
+    pub fn source_roots_mut(&mut self) -> &mut Vec<String> {
+        &mut self.source_roots
+    }
+
+    pub fn files(&self) -> &BTreeMap<String, FileCoverage> {
+        &self.files
+    }
+
+    pub fn files_mut(&mut self) -> &mut BTreeMap<String, FileCoverage> {
+        &mut self.files
+    }
+
+    /// Coverage of the file at `path`, added if it doesn't exist yet.
+    pub fn file_mut(&mut self, path: &str) -> &mut FileCoverage {
+        self.files.entry(path.to_string()).or_default()
+    }
+
+    /// Merges the coverage of another run into this one: hits of the same line, branch,
+    /// function or region are added up, everything else is kept.
+    pub fn merge(mut self, other: Self) -> Self {
+        if self.name.is_none() {
+            self.name = other.name;
+        }
+        for root in other.source_roots {
+            if !self.source_roots.contains(&root) {
+                self.source_roots.push(root);
+            }
+        }
+        for (path, file) in other.files {
+            match self.files.entry(path) {
+                Entry::Occupied(mut occ) => occ.get_mut().merge(file),
+                Entry::Vacant(vac) => {
+                    vac.insert(file);
+                }
+            }
+        }
+        self
+    }
+
+    pub fn lines_found(&self) -> usize {
+        self.files.values().map(FileCoverage::lines_found).sum()
+    }
+
+    pub fn lines_hit(&self) -> usize {
+        self.files.values().map(FileCoverage::lines_hit).sum()
+    }
+
+    pub fn branches_found(&self) -> usize {
+        self.files.values().map(FileCoverage::branches_found).sum()
+    }
+
+    pub fn branches_hit(&self) -> usize {
+        self.files.values().map(FileCoverage::branches_hit).sum()
+    }
+
+    pub fn functions_found(&self) -> usize {
+        self.files.values().map(FileCoverage::functions_found).sum()
+    }
+
+    pub fn functions_hit(&self) -> usize {
+        self.files.values().map(FileCoverage::functions_hit).sum()
+    }
+}
+
+impl FileCoverage {
+    pub fn new(package: Option<String>) -> Self {
+        Self {
+            package,
+            ..Default::default()
+        }
+    }
+
+    pub fn package(&self) -> Option<&String> {
+        self.package.as_ref()
+    }
+
+    pub fn package_mut(&mut self) -> &mut Option<String> {
+        &mut self.package
+    }
+
+    pub fn lines(&self) -> &BTreeMap<u32, u64> {
+        &self.lines
+    }
+
+    pub fn lines_mut(&mut self) -> &mut BTreeMap<u32, u64> {
+        &mut self.lines
+    }
+
+    pub fn branches(&self) -> &Vec<BranchCoverage> {
+        &self.branches
+    }
+
+    pub fn branches_mut(&mut self) -> &mut Vec<BranchCoverage> {
+        &mut self.branches
+    }
+
+    pub fn functions(&self) -> &Vec<FunctionCoverage> {
+        &self.functions
+    }
+
+    pub fn functions_mut(&mut self) -> &mut Vec<FunctionCoverage> {
+        &mut self.functions
+    }
+
+    pub fn regions(&self) -> &Vec<RegionCoverage> {
+        &self.regions
+    }
+
+    pub fn regions_mut(&mut self) -> &mut Vec<RegionCoverage> {
+        &mut self.regions
+    }
+
+    /// Adds hits to a line, creating it if it doesn't exist yet.
+    pub fn add_line_hits(&mut self, line: u32, hits: u64) {
+        let count = self.lines.entry(line).or_default();
+        *count = count.saturating_add(hits);
+    }
+
+    pub fn lines_found(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn lines_hit(&self) -> usize {
+        self.lines.values().filter(|hits| **hits > 0).count()
+    }
+
+    pub fn branches_found(&self) -> usize {
+        self.branches.len()
+    }
+
+    pub fn branches_hit(&self) -> usize {
+        self.branches
+            .iter()
+            .filter(|b| b.taken.is_some_and(|taken| taken > 0))
+            .count()
+    }
+
+    pub fn functions_found(&self) -> usize {
+        self.functions.len()
+    }
+
+    pub fn functions_hit(&self) -> usize {
+        self.functions.iter().filter(|f| f.hits > 0).count()
+    }
+
+    fn merge(&mut self, other: Self) {
+        if self.package.is_none() {
+            self.package = other.package;
+        }
+        for (line, hits) in other.lines {
+            self.add_line_hits(line, hits);
+        }
+
+        let mut branches: HashMap<(u32, u32, u32), usize> = self
+            .branches
+            .iter()
+            .enumerate()
+            .map(|(i, b)| ((b.line, b.block, b.branch), i))
+            .collect();
+        for branch in other.branches {
+            match branches.get(&(branch.line, branch.block, branch.branch)) {
+                Some(&i) => {
+                    let existing = &mut self.branches[i];
+                    existing.taken = match (existing.taken, branch.taken) {
+                        (Some(a), Some(b)) => Some(a.saturating_add(b)),
+                        (a, b) => a.or(b),
+                    };
+                }
+                None => {
+                    branches.insert(
+                        (branch.line, branch.block, branch.branch),
+                        self.branches.len(),
+                    );
+                    self.branches.push(branch);
+                }
+            }
+        }
+
+        let mut functions: HashMap<String, usize> = self
+            .functions
+            .iter()
+            .enumerate()
+            .map(|(i, f)| (f.name.clone(), i))
+            .collect();
+        for function in other.functions {
+            match functions.get(&function.name) {
+                Some(&i) => {
+                    let existing = &mut self.functions[i];
+                    existing.hits = existing.hits.saturating_add(function.hits);
+                    existing.start_line = existing.start_line.or(function.start_line);
+                    existing.end_line = existing.end_line.or(function.end_line);
+                }
+                None => {
+                    functions.insert(function.name.clone(), self.functions.len());
+                    self.functions.push(function);
+                }
+            }
+        }
+
+        let mut regions: HashMap<(u32, u32, u32, u32), usize> = self
+            .regions
+            .iter()
+            .enumerate()
+            .map(|(i, r)| (r.position(), i))
+            .collect();
+        for region in other.regions {
+            match regions.get(&region.position()) {
+                Some(&i) => {
+                    let existing = &mut self.regions[i];
+                    existing.hits = existing.hits.saturating_add(region.hits);
+                }
+                None => {
+                    regions.insert(region.position(), self.regions.len());
+                    self.regions.push(region);
+                }
+            }
+        }
+    }
+}
+
+impl BranchCoverage {
+    pub fn new(line: u32, block: u32, branch: u32, taken: Option<u64>) -> Self {
+        Self {
+            line,
+            block,
+            branch,
+            taken,
+        }
+    }
+
+    pub fn line(&self) -> u32 {
+        self.line
+    }
+
+    pub fn line_mut(&mut self) -> &mut u32 {
+        &mut self.line
+    }
+
+    pub fn block(&self) -> u32 {
+        self.block
+    }
+
+    pub fn block_mut(&mut self) -> &mut u32 {
+        &mut self.block
+    }
+
+    pub fn branch(&self) -> u32 {
+        self.branch
+    }
+
+    pub fn branch_mut(&mut self) -> &mut u32 {
+        &mut self.branch
+    }
+
+    pub fn taken(&self) -> Option<u64> {
+        self.taken
+    }
+
+    pub fn taken_mut(&mut self) -> &mut Option<u64> {
+        &mut self.taken
+    }
+}
+
+impl FunctionCoverage {
+    pub fn new(name: String, start_line: Option<u32>, end_line: Option<u32>, hits: u64) -> Self {
+        Self {
+            name,
+            start_line,
+            end_line,
+            hits,
+        }
+    }
+
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
+
+    pub fn start_line(&self) -> Option<u32> {
+        self.start_line
+    }
+
+    pub fn start_line_mut(&mut self) -> &mut Option<u32> {
+        &mut self.start_line
+    }
+
+    pub fn end_line(&self) -> Option<u32> {
+        self.end_line
+    }
+
+    pub fn end_line_mut(&mut self) -> &mut Option<u32> {
+        &mut self.end_line
+    }
+
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    pub fn hits_mut(&mut self) -> &mut u64 {
+        &mut self.hits
+    }
+}
+
+impl RegionCoverage {
+    pub fn new(
+        start_line: u32,
+        start_col: u32,
+        end_line: u32,
+        end_col: u32,
+        number_of_statements: u32,
+        hits: u64,
+    ) -> Self {
+        Self {
+            start_line,
+            start_col,
+            end_line,
+            end_col,
+            number_of_statements,
+            hits,
+        }
+    }
+
+    pub fn start_line(&self) -> u32 {
+        self.start_line
+    }
+
+    pub fn start_line_mut(&mut self) -> &mut u32 {
+        &mut self.start_line
+    }
+
+    pub fn start_col(&self) -> u32 {
+        self.start_col
+    }
+
+    pub fn start_col_mut(&mut self) -> &mut u32 {
+        &mut self.start_col
+    }
+
+    pub fn end_line(&self) -> u32 {
+        self.end_line
+    }
+
+    pub fn end_line_mut(&mut self) -> &mut u32 {
+        &mut self.end_line
+    }
+
+    pub fn end_col(&self) -> u32 {
+        self.end_col
+    }
+
+    pub fn end_col_mut(&mut self) -> &mut u32 {
+        &mut self.end_col
+    }
+
+    pub fn number_of_statements(&self) -> u32 {
+        self.number_of_statements
+    }
+
+    pub fn number_of_statements_mut(&mut self) -> &mut u32 {
+        &mut self.number_of_statements
+    }
+
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    pub fn hits_mut(&mut self) -> &mut u64 {
+        &mut self.hits
+    }
+
+    fn position(&self) -> (u32, u32, u32, u32) {
+        (self.start_line, self.start_col, self.end_line, self.end_col)
+    }
+}
@@ -0,0 +1,218 @@
+//! Detection of the format of coverage files by their content, for inputs whose file names can't
+//! be relied on.
+
+use crate::cobertura::CoberturaReport;
+use crate::coverage::CoverageReport;
+use crate::go::covdata::{COUNTER_FILE_MAGIC, META_FILE_MAGIC, MetaFile};
+use crate::go::{GoProfileMode, GoReport};
+use crate::jacoco::{CsvReport, XmlReport};
+use crate::lcov::LcovReport;
+use crate::{Error, Result};
+
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+#[cfg(feature = "serialization")]
+use serde::{Deserialize, Serialize};
+
+/// Header block of execution data files: block type followed by the magic number.
+const JACOCO_EXEC_HEADER: [u8; 3] = [0x01, 0xC0, 0xC0];
+const JACOCO_XML_DOCTYPE: &[u8] = b"-//JACOCO//DTD Report";
+const COBERTURA_MARKER: &[u8] = b"cobertura";
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum CoverageFormat {
+    /// Text profile written by `go test -coverprofile`
+    GoProfile,
+    /// `covmeta.*` file of a `GOCOVERDIR`
+    GoCovdataMeta,
+    /// `covcounters.*` file of a `GOCOVERDIR`
+    GoCovdataCounters,
+    /// JaCoCo's `jacoco.exec`
+    JacocoExec,
+    JacocoXml,
+    JacocoCsv,
+    /// LCOV tracefile
+    Lcov,
+    CoberturaXml,
+}
+
+/// How sure a detection is, ordered from least to most certain.
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Confidence {
+    /// The input looks like the format, but isn't marked as such, e.g. an XML document with the
+    /// right root element but without a doctype
+    Likely,
+    /// The input starts with the format's magic number, header or doctype, or names the format
+    Certain,
+}
+
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct Detection {
+    format: CoverageFormat,
+    confidence: Confidence,
+}
+
+impl Display for CoverageFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::GoProfile => "Go coverage profile",
+            Self::GoCovdataMeta => "Go coverage meta-data file",
+            Self::GoCovdataCounters => "Go coverage counter data file",
+            Self::JacocoExec => "JaCoCo execution data file",
+            Self::JacocoXml => "JaCoCo XML report",
+            Self::JacocoCsv => "JaCoCo CSV report",
+            Self::Lcov => "LCOV tracefile",
+            Self::CoberturaXml => "Cobertura XML report",
+        };
+        f.write_str(name)
+    }
+}
+
+impl Detection {
+    pub fn new(format: CoverageFormat, confidence: Confidence) -> Self {
+        Self { format, confidence }
+    }
+
+    pub fn format(&self) -> CoverageFormat {
+        self.format
+    }
+
+    pub fn confidence(&self) -> Confidence {
+        self.confidence
+    }
+}
+
+/// Detects the format of an input from its first bytes, `None` if it isn't a supported format.
+/// The first kilobyte is enough for all formats, a shorter prefix may only lower the confidence.
+pub fn detect(bytes: &[u8]) -> Option<Detection> {
+    use Confidence::*;
+    use CoverageFormat::*;
+
+    if bytes.starts_with(&JACOCO_EXEC_HEADER) {
+        return Some(Detection::new(JacocoExec, Certain));
+    }
+    if bytes.starts_with(&META_FILE_MAGIC) {
+        return Some(Detection::new(GoCovdataMeta, Certain));
+    }
+    if bytes.starts_with(&COUNTER_FILE_MAGIC) {
+        return Some(Detection::new(GoCovdataCounters, Certain));
+    }
+
+    let text = bytes
+        .strip_prefix(UTF8_BOM)
+        .unwrap_or(bytes)
+        .trim_ascii_start();
+    let first_line = text.split(|&b| b == b'\n').next().unwrap_or_default();
+    let first_line = first_line.trim_ascii_end();
+
+    if let Some(mode) = first_line.strip_prefix(b"mode:") {
+        let known_mode = std::str::from_utf8(mode)
+            .is_ok_and(|mode| mode.trim().parse::<GoProfileMode>().is_ok());
+        return Some(Detection::new(
+            GoProfile,
+            if known_mode { Certain } else { Likely },
+        ));
+    }
+    if first_line == CsvReport::header().as_bytes() {
+        return Some(Detection::new(JacocoCsv, Certain));
+    }
+    if first_line.starts_with(b"GROUP,PACKAGE,CLASS,") {
+        return Some(Detection::new(JacocoCsv, Likely));
+    }
+    if first_line.starts_with(b"TN:") || first_line.starts_with(b"SF:") {
+        return Some(Detection::new(Lcov, Certain));
+    }
+    // coverage.py has no doctype, but mentions Cobertura in a comment
+    let (format, marker) = match xml_root_element(text)? {
+        b"report" => (JacocoXml, JACOCO_XML_DOCTYPE),
+        b"coverage" => (CoberturaXml, COBERTURA_MARKER),
+        _ => return None,
+    };
+    let confidence = if contains(text, marker) {
+        Certain
+    } else {
+        Likely
+    };
+    Some(Detection::new(format, confidence))
+}
+
+/// Name of the first element, after the declaration, doctype and comments.
+fn xml_root_element(mut text: &[u8]) -> Option<&[u8]> {
+    loop {
+        text = text.trim_ascii_start();
+        let end_marker: &[u8] = if text.starts_with(b"<?") {
+            b"?>"
+        } else if text.starts_with(b"<!--") {
+            b"-->"
+        } else if text.starts_with(b"<!") {
+            b">"
+        } else {
+            break;
+        };
+        let end = text
+            .windows(end_marker.len())
+            .position(|w| w == end_marker)?;
+        text = &text[end + end_marker.len()..];
+    }
+    let name = text.strip_prefix(b"<")?;
+    let len = name
+        .iter()
+        .position(|&b| b.is_ascii_whitespace() || b == b'>' || b == b'/')
+        .unwrap_or(name.len());
+    Some(&name[..len])
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|w| w == needle)
+}
+
+/// Opens a coverage file of any format that can be converted on its own and converts it, see
+/// [`read_any`]. A directory is read as a `GOCOVERDIR`.
+///
+/// # Errors
+///
+/// See [`read_any`] and [`GoReport::from_covdata_dir`]
+pub fn open_any<P: AsRef<Path>>(path: P) -> Result<CoverageReport> {
+    let path = path.as_ref();
+    if path.is_dir() {
+        return Ok((&GoReport::from_covdata_dir(path)?).into());
+    }
+    read_any(&mut BufReader::new(File::open(path)?))
+}
+
+/// Detects the format of the input and converts it. Go meta-data files have no counters, so all
+/// their blocks are uncovered.
+///
+/// # Errors
+///
+/// Will return `Error::UnknownFormat` if the format isn't detected,
+/// `Error::UnsupportedConversion` for formats that need further input to be converted, like
+/// execution data without class files, and the reader's error if the input is invalid
+pub fn read_any<R: Read>(r: &mut R) -> Result<CoverageReport> {
+    let mut bytes = vec![];
+    r.read_to_end(&mut bytes)?;
+    let detection = detect(&bytes).ok_or(Error::UnknownFormat)?;
+
+    let mut input = bytes.as_slice();
+    let report = match detection.format() {
+        CoverageFormat::GoProfile => (&GoReport::from_buf_read(&mut input)?).into(),
+        CoverageFormat::GoCovdataMeta => {
+            let meta_file = MetaFile::from_read(&mut input)?;
+            (&GoReport::from_covdata(&[meta_file], &[])?).into()
+        }
+        CoverageFormat::JacocoXml => (&XmlReport::from_buf_read(&mut input)?).into(),
+        CoverageFormat::Lcov => (&LcovReport::from_buf_read(&mut input)?).into(),
+        CoverageFormat::CoberturaXml => (&CoberturaReport::from_buf_read(&mut input)?).into(),
+        format @ (CoverageFormat::GoCovdataCounters
+        | CoverageFormat::JacocoExec
+        | CoverageFormat::JacocoCsv) => return Err(Error::UnsupportedConversion(format)),
+    };
+    Ok(report)
+}
@@ -0,0 +1,74 @@
+//! Conversion between [`GoReport`] and the format-neutral [`CoverageReport`].
+
+use super::{GoProfileBlock, GoProfileMode, GoReport};
+use crate::coverage::{CoverageReport, FileCoverage, RegionCoverage};
+
+/// Every block becomes a region of its file. A line gets the highest count of the blocks it's
+/// part of, the package is the import path of the file's directory.
+impl From<&GoReport> for CoverageReport {
+    fn from(report: &GoReport) -> Self {
+        let mut coverage = CoverageReport::new(None);
+        for (filename, blocks) in report.blocks_by_file() {
+            let package = filename.rsplit_once('/').map(|(dir, _)| dir.to_string());
+            let file = coverage.file_mut(filename);
+            *file.package_mut() = package;
+            for block in blocks {
+                let hits = u64::from(block.count);
+                for line in block.start_line..=block.end_line {
+                    let line_hits = file.lines_mut().entry(line).or_default();
+                    *line_hits = (*line_hits).max(hits);
+                }
+                file.regions_mut().push(RegionCoverage::new(
+                    block.start_line,
+                    block.start_col,
+                    block.end_line,
+                    block.end_col,
+                    block.number_of_statements,
+                    hits,
+                ));
+            }
+        }
+        coverage
+    }
+}
+
+impl GoReport {
+    /// Converts the regions of every file back into blocks. Files without regions get a block
+    /// per line, from its first column to the start of the next line, with a single statement.
+    /// Counts are clamped to `u32::MAX` and to 1 in `set` mode.
+    pub fn from_coverage(coverage: &CoverageReport, mode: GoProfileMode) -> Self {
+        let mut blocks = vec![];
+        for (path, file) in coverage.files() {
+            blocks.extend(file_blocks(path, file));
+        }
+        blocks.sort();
+        GoReport::new(GoProfileMode::Count, blocks).with_mode(mode)
+    }
+}
+
+fn file_blocks(path: &str, file: &FileCoverage) -> Vec<GoProfileBlock> {
+    let count = |hits: u64| u32::try_from(hits).unwrap_or(u32::MAX);
+    if file.regions().is_empty() {
+        return file
+            .lines()
+            .iter()
+            .map(|(&line, &hits)| {
+                GoProfileBlock::new(path.to_string(), line, 1, line + 1, 1, 1, count(hits))
+            })
+            .collect();
+    }
+    file.regions()
+        .iter()
+        .map(|region| {
+            GoProfileBlock::new(
+                path.to_string(),
+                region.start_line(),
+                region.start_col(),
+                region.end_line(),
+                region.end_col(),
+                region.number_of_statements(),
+                count(region.hits()),
+            )
+        })
+        .collect()
+}
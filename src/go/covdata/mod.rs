@@ -0,0 +1,623 @@
+//! Binary coverage data files written by binaries built with `go build -cover` (Go 1.20+) into
+//! `GOCOVERDIR`: `covmeta.<hash>` meta-data files describing the coverable units of every
+//! package, and `covcounters.<hash>.<pid>.<nanotime>` counter data files with the counter
+//! values of a single run.
+
+pub(super) use super::{GoCoverageError, Result};
+
+pub mod reader;
+pub mod writer;
+
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+
+#[cfg(feature = "serialization")]
+use serde::{Deserialize, Serialize};
+
+pub(crate) const META_FILE_MAGIC: [u8; 4] = [0x00, 0x63, 0x76, 0x6d];
+const META_FILE_VERSION: u32 = 1;
+const META_FILE_HEADER_SIZE: usize = 56;
+const PACKAGE_META_HEADER_SIZE: usize = 44;
+
+pub(crate) const COUNTER_FILE_MAGIC: [u8; 4] = [0x00, 0x63, 0x77, 0x6d];
+const COUNTER_FILE_VERSION: u32 = 1;
+const COUNTER_FILE_HEADER_SIZE: usize = 32;
+const COUNTER_FILE_FOOTER_SIZE: usize = 16;
+
+/// Prefix of meta-data file names, followed by the hex encoded meta file hash.
+pub const META_FILE_PREFIX: &str = "covmeta.";
+/// Prefix of counter data file names, followed by the hex encoded meta file hash, the pid and
+/// the time the file was written.
+pub const COUNTER_FILE_PREFIX: &str = "covcounters.";
+
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[repr(u8)]
+pub enum CounterMode {
+    Set = 1,
+    Count = 2,
+    Atomic = 3,
+    /// Binary only registers its packages, no counters are emitted
+    RegOnly = 4,
+    TestMain = 5,
+}
+
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[repr(u8)]
+pub enum CounterGranularity {
+    /// One counter per coverable unit
+    PerBlock = 1,
+    /// A single counter per function
+    PerFunc = 2,
+}
+
+/// Encoding of the counter values in a counter data file.
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+#[repr(u8)]
+pub enum CounterFlavor {
+    /// Fixed size 32 bit values
+    Raw = 1,
+    /// ULEB128 encoded values, which is what the Go runtime writes
+    #[default]
+    Uleb128 = 2,
+}
+
+/// Meta-data and counter data files of a `GOCOVERDIR`.
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct CovData {
+    meta_files: Vec<MetaFile>,
+    counter_files: Vec<CounterFile>,
+}
+
+/// Contents of a `covmeta.*` file.
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct MetaFile {
+    hash: [u8; 16],
+    mode: CounterMode,
+    granularity: CounterGranularity,
+    packages: Vec<PackageMeta>,
+}
+
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct PackageMeta {
+    name: String,
+    path: String,
+    module_path: String,
+    hash: [u8; 16],
+    funcs: Vec<FuncMeta>,
+}
+
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct FuncMeta {
+    name: String,
+    /// Source file of the function, prefixed with the package import path
+    src_file: String,
+    units: Vec<CoverableUnit>,
+    /// `true` for function literals
+    is_literal: bool,
+}
+
+/// A block of statements with a single counter, the equivalent of a [`super::GoProfileBlock`]
+/// without its file and count.
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub struct CoverableUnit {
+    start_line: u32,
+    start_col: u32,
+    end_line: u32,
+    end_col: u32,
+    number_of_statements: u32,
+}
+
+/// Contents of a `covcounters.*` file.
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct CounterFile {
+    meta_hash: [u8; 16],
+    flavor: CounterFlavor,
+    segments: Vec<CounterSegment>,
+}
+
+/// Counters of a single run. Files written by the runtime have a single segment, merged files
+/// may have several.
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct CounterSegment {
+    /// Command line and environment of the run, e.g. `argc`, `argv0`, `GOOS` and `GOARCH`
+    args: Vec<(String, String)>,
+    funcs: Vec<FuncCounters>,
+}
+
+/// Counter values of a function, which is identified by its index in the package and the index
+/// of the package in the meta-data file.
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct FuncCounters {
+    package_index: u32,
+    func_index: u32,
+    counters: Vec<u32>,
+}
+
+impl TryFrom<u8> for CounterMode {
+    type Error = GoCoverageError;
+
+    fn try_from(value: u8) -> core::result::Result<Self, Self::Error> {
+        match value {
+            1 => Ok(Self::Set),
+            2 => Ok(Self::Count),
+            3 => Ok(Self::Atomic),
+            4 => Ok(Self::RegOnly),
+            5 => Ok(Self::TestMain),
+            _ => Err(GoCoverageError::InvalidCounterMode(value)),
+        }
+    }
+}
+
+impl TryFrom<u8> for CounterGranularity {
+    type Error = GoCoverageError;
+
+    fn try_from(value: u8) -> core::result::Result<Self, Self::Error> {
+        match value {
+            1 => Ok(Self::PerBlock),
+            2 => Ok(Self::PerFunc),
+            _ => Err(GoCoverageError::InvalidCounterGranularity(value)),
+        }
+    }
+}
+
+impl TryFrom<u8> for CounterFlavor {
+    type Error = GoCoverageError;
+
+    fn try_from(value: u8) -> core::result::Result<Self, Self::Error> {
+        match value {
+            1 => Ok(Self::Raw),
+            2 => Ok(Self::Uleb128),
+            _ => Err(GoCoverageError::InvalidCounterFlavor(value)),
+        }
+    }
+}
+
+/// Hex encoding of a meta file hash, as used in the names of meta-data and counter data files.
+pub fn hash_to_hex(hash: &[u8; 16]) -> String {
+    hash.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+impl CovData {
+    pub fn new(meta_files: Vec<MetaFile>, counter_files: Vec<CounterFile>) -> Self {
+        Self {
+            meta_files,
+            counter_files,
+        }
+    }
+
+    pub fn meta_files(&self) -> &Vec<MetaFile> {
+        &self.meta_files
+    }
+
+    pub fn meta_files_mut(&mut self) -> &mut Vec<MetaFile> {
+        &mut self.meta_files
+    }
+
+    pub fn counter_files(&self) -> &Vec<CounterFile> {
+        &self.counter_files
+    }
+
+    pub fn counter_files_mut(&mut self) -> &mut Vec<CounterFile> {
+        &mut self.counter_files
+    }
+
+    /// See [`CovData::try_merge_all`] for details.
+    ///
+    /// # Errors
+    ///
+    /// See [`CovData::try_merge_all`].
+    pub fn try_merge(self, other: Self) -> Result<Self> {
+        Self::try_merge_all([self, other])
+    }
+
+    /// Merges coverage data of any number of directories or runs, like `go tool covdata merge`.
+    ///
+    /// Meta-data files are deduplicated by their hash. All counter files of the same meta-data
+    /// file are merged into a single counter file with a single segment, keeping the counters
+    /// per package and function: counters are OR-ed in `set` mode and added up otherwise.
+    /// Arguments of the runs are kept if all runs agree on them.
+    ///
+    /// # Errors
+    ///
+    /// Will return `GoCoverageError::MissingCovdataMetaFile` if a counter file has no matching
+    /// meta-data file and `GoCoverageError::InvalidCounterIndex` if it references a function that
+    /// doesn't exist.
+    pub fn try_merge_all<I: IntoIterator<Item = Self>>(cov_datas: I) -> Result<Self> {
+        let mut meta_files: Vec<MetaFile> = vec![];
+        let mut counter_files = vec![];
+        for cov_data in cov_datas {
+            for meta_file in cov_data.meta_files {
+                if !meta_files.iter().any(|m| m.hash == meta_file.hash) {
+                    meta_files.push(meta_file);
+                }
+            }
+            counter_files.extend(cov_data.counter_files);
+        }
+
+        // Merged segment and index of each function in it, per meta file
+        type MergedSegment = (CounterSegment, HashMap<(u32, u32), usize>);
+        let mut merged: Vec<Option<MergedSegment>> = vec![None; meta_files.len()];
+        for counter_file in counter_files {
+            let meta_index = meta_files
+                .iter()
+                .position(|m| m.hash == counter_file.meta_hash)
+                .ok_or_else(|| {
+                    GoCoverageError::MissingCovdataMetaFile(hash_to_hex(&counter_file.meta_hash))
+                })?;
+            let meta_file = &meta_files[meta_index];
+
+            for segment in counter_file.segments {
+                let (merged_segment, func_indices) = match &mut merged[meta_index] {
+                    Some(merged) => {
+                        // Keep only the arguments all runs agree on
+                        merged.0.args.retain(|arg| segment.args.contains(arg));
+                        merged
+                    }
+                    merged => merged.insert((
+                        CounterSegment::new(segment.args.clone(), vec![]),
+                        HashMap::new(),
+                    )),
+                };
+
+                for func in segment.funcs {
+                    meta_file.check_counter_index(&func)?;
+                    match func_indices.entry((func.package_index, func.func_index)) {
+                        Entry::Occupied(occ) => {
+                            merged_segment.funcs[*occ.get()].merge(&func, meta_file.mode);
+                        }
+                        Entry::Vacant(vac) => {
+                            vac.insert(merged_segment.funcs.len());
+                            merged_segment.funcs.push(func);
+                        }
+                    }
+                }
+            }
+        }
+
+        let counter_files = meta_files
+            .iter()
+            .zip(merged)
+            .filter_map(|(meta_file, merged)| {
+                let (mut segment, _) = merged?;
+                segment
+                    .funcs
+                    .sort_by_key(|func| (func.package_index, func.func_index));
+                Some(CounterFile::new(
+                    meta_file.hash,
+                    CounterFlavor::default(),
+                    vec![segment],
+                ))
+            })
+            .collect();
+
+        Ok(Self {
+            meta_files,
+            counter_files,
+        })
+    }
+}
+
+impl MetaFile {
+    pub fn new(
+        hash: [u8; 16],
+        mode: CounterMode,
+        granularity: CounterGranularity,
+        packages: Vec<PackageMeta>,
+    ) -> Self {
+        Self {
+            hash,
+            mode,
+            granularity,
+            packages,
+        }
+    }
+
+    pub fn hash(&self) -> &[u8; 16] {
+        &self.hash
+    }
+
+    pub fn hash_mut(&mut self) -> &mut [u8; 16] {
+        &mut self.hash
+    }
+
+    pub fn mode(&self) -> CounterMode {
+        self.mode
+    }
+
+    pub fn mode_mut(&mut self) -> &mut CounterMode {
+        &mut self.mode
+    }
+
+    pub fn granularity(&self) -> CounterGranularity {
+        self.granularity
+    }
+
+    pub fn granularity_mut(&mut self) -> &mut CounterGranularity {
+        &mut self.granularity
+    }
+
+    pub fn packages(&self) -> &Vec<PackageMeta> {
+        &self.packages
+    }
+
+    pub fn packages_mut(&mut self) -> &mut Vec<PackageMeta> {
+        &mut self.packages
+    }
+
+    /// # Errors
+    ///
+    /// Will return `GoCoverageError::InvalidCounterIndex` if the function of `func` doesn't exist
+    fn check_counter_index(&self, func: &FuncCounters) -> Result<()> {
+        let exists = self
+            .packages
+            .get(func.package_index as usize)
+            .is_some_and(|p| (func.func_index as usize) < p.funcs.len());
+        if !exists {
+            return Err(GoCoverageError::InvalidCounterIndex {
+                package: func.package_index,
+                func: func.func_index,
+            }
+            .into());
+        }
+        Ok(())
+    }
+}
+
+impl PackageMeta {
+    pub fn new(
+        name: String,
+        path: String,
+        module_path: String,
+        hash: [u8; 16],
+        funcs: Vec<FuncMeta>,
+    ) -> Self {
+        Self {
+            name,
+            path,
+            module_path,
+            hash,
+            funcs,
+        }
+    }
+
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
+
+    /// Import path of the package
+    pub fn path(&self) -> &String {
+        &self.path
+    }
+
+    pub fn path_mut(&mut self) -> &mut String {
+        &mut self.path
+    }
+
+    pub fn module_path(&self) -> &String {
+        &self.module_path
+    }
+
+    pub fn module_path_mut(&mut self) -> &mut String {
+        &mut self.module_path
+    }
+
+    pub fn hash(&self) -> &[u8; 16] {
+        &self.hash
+    }
+
+    pub fn hash_mut(&mut self) -> &mut [u8; 16] {
+        &mut self.hash
+    }
+
+    pub fn funcs(&self) -> &Vec<FuncMeta> {
+        &self.funcs
+    }
+
+    pub fn funcs_mut(&mut self) -> &mut Vec<FuncMeta> {
+        &mut self.funcs
+    }
+}
+
+impl FuncMeta {
+    pub fn new(
+        name: String,
+        src_file: String,
+        units: Vec<CoverableUnit>,
+        is_literal: bool,
+    ) -> Self {
+        Self {
+            name,
+            src_file,
+            units,
+            is_literal,
+        }
+    }
+
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
+
+    pub fn src_file(&self) -> &String {
+        &self.src_file
+    }
+
+    pub fn src_file_mut(&mut self) -> &mut String {
+        &mut self.src_file
+    }
+
+    pub fn units(&self) -> &Vec<CoverableUnit> {
+        &self.units
+    }
+
+    pub fn units_mut(&mut self) -> &mut Vec<CoverableUnit> {
+        &mut self.units
+    }
+
+    pub fn is_literal(&self) -> bool {
+        self.is_literal
+    }
+
+    pub fn is_literal_mut(&mut self) -> &mut bool {
+        &mut self.is_literal
+    }
+}
+
+impl CoverableUnit {
+    pub fn new(
+        start_line: u32,
+        start_col: u32,
+        end_line: u32,
+        end_col: u32,
+        number_of_statements: u32,
+    ) -> Self {
+        Self {
+            start_line,
+            start_col,
+            end_line,
+            end_col,
+            number_of_statements,
+        }
+    }
+
+    pub fn start_line(&self) -> u32 {
+        self.start_line
+    }
+
+    pub fn start_col(&self) -> u32 {
+        self.start_col
+    }
+
+    pub fn end_line(&self) -> u32 {
+        self.end_line
+    }
+
+    pub fn end_col(&self) -> u32 {
+        self.end_col
+    }
+
+    pub fn number_of_statements(&self) -> u32 {
+        self.number_of_statements
+    }
+}
+
+impl CounterFile {
+    pub fn new(meta_hash: [u8; 16], flavor: CounterFlavor, segments: Vec<CounterSegment>) -> Self {
+        Self {
+            meta_hash,
+            flavor,
+            segments,
+        }
+    }
+
+    /// Hash of the meta-data file the counters belong to
+    pub fn meta_hash(&self) -> &[u8; 16] {
+        &self.meta_hash
+    }
+
+    pub fn meta_hash_mut(&mut self) -> &mut [u8; 16] {
+        &mut self.meta_hash
+    }
+
+    pub fn flavor(&self) -> CounterFlavor {
+        self.flavor
+    }
+
+    pub fn flavor_mut(&mut self) -> &mut CounterFlavor {
+        &mut self.flavor
+    }
+
+    pub fn segments(&self) -> &Vec<CounterSegment> {
+        &self.segments
+    }
+
+    pub fn segments_mut(&mut self) -> &mut Vec<CounterSegment> {
+        &mut self.segments
+    }
+}
+
+impl CounterSegment {
+    pub fn new(args: Vec<(String, String)>, funcs: Vec<FuncCounters>) -> Self {
+        Self { args, funcs }
+    }
+
+    pub fn args(&self) -> &Vec<(String, String)> {
+        &self.args
+    }
+
+    pub fn args_mut(&mut self) -> &mut Vec<(String, String)> {
+        &mut self.args
+    }
+
+    pub fn funcs(&self) -> &Vec<FuncCounters> {
+        &self.funcs
+    }
+
+    pub fn funcs_mut(&mut self) -> &mut Vec<FuncCounters> {
+        &mut self.funcs
+    }
+}
+
+impl FuncCounters {
+    pub fn new(package_index: u32, func_index: u32, counters: Vec<u32>) -> Self {
+        Self {
+            package_index,
+            func_index,
+            counters,
+        }
+    }
+
+    pub fn package_index(&self) -> u32 {
+        self.package_index
+    }
+
+    pub fn package_index_mut(&mut self) -> &mut u32 {
+        &mut self.package_index
+    }
+
+    pub fn func_index(&self) -> u32 {
+        self.func_index
+    }
+
+    pub fn func_index_mut(&mut self) -> &mut u32 {
+        &mut self.func_index
+    }
+
+    pub fn counters(&self) -> &Vec<u32> {
+        &self.counters
+    }
+
+    pub fn counters_mut(&mut self) -> &mut Vec<u32> {
+        &mut self.counters
+    }
+
+    fn merge(&mut self, other: &Self, mode: CounterMode) {
+        if self.counters.len() < other.counters.len() {
+            self.counters.resize(other.counters.len(), 0);
+        }
+        for (counter, &other) in self.counters.iter_mut().zip(&other.counters) {
+            *counter = match mode {
+                CounterMode::Set => *counter | other,
+                _ => counter.saturating_add(other),
+            };
+        }
+    }
+}
@@ -0,0 +1,477 @@
+use super::{
+    COUNTER_FILE_FOOTER_SIZE, COUNTER_FILE_HEADER_SIZE, COUNTER_FILE_MAGIC, COUNTER_FILE_PREFIX,
+    COUNTER_FILE_VERSION, CounterFile, CounterFlavor, CounterGranularity, CounterMode,
+    CounterSegment, CovData, CoverableUnit, FuncCounters, FuncMeta, GoCoverageError,
+    META_FILE_HEADER_SIZE, META_FILE_MAGIC, META_FILE_PREFIX, META_FILE_VERSION, MetaFile,
+    PACKAGE_META_HEADER_SIZE, PackageMeta, Result, hash_to_hex,
+};
+use crate::go::{GoProfileBlock, GoProfileMode, GoReport};
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+impl MetaFile {
+    /// Reads a `covmeta.*` file.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `GoCoverageError::InvalidCovdataMagic` or
+    /// `GoCoverageError::UnsupportedCovdataVersion` if the input isn't a supported meta-data file
+    /// and with `GoCoverageError::TruncatedCovdata` if it ends early.
+    pub fn from_read<R: Read>(r: &mut R) -> Result<Self> {
+        let mut bytes = vec![];
+        r.read_to_end(&mut bytes)?;
+        let mut r = SliceReader::new(&bytes);
+
+        if r.bytes(4)? != META_FILE_MAGIC {
+            return Err(GoCoverageError::InvalidCovdataMagic.into());
+        }
+        let version = r.u32()?;
+        if version != META_FILE_VERSION {
+            return Err(GoCoverageError::UnsupportedCovdataVersion(version).into());
+        }
+        let _total_length = r.u64()?;
+        let entries = usize::try_from(r.u64()?)?;
+        let hash = r.hash()?;
+        let _string_table_offset = r.u32()?;
+        let _string_table_length = r.u32()?;
+        let mode = CounterMode::try_from(r.u8()?)?;
+        let granularity = CounterGranularity::try_from(r.u8()?)?;
+        r.seek(META_FILE_HEADER_SIZE);
+
+        let mut offsets = Vec::with_capacity(entries.min(bytes.len()));
+        for _ in 0..entries {
+            offsets.push(usize::try_from(r.u64()?)?);
+        }
+        let mut lengths = Vec::with_capacity(offsets.len());
+        for _ in 0..entries {
+            lengths.push(usize::try_from(r.u64()?)?);
+        }
+
+        // The file level string table is not referenced by the package blobs, they carry their
+        // own string tables
+        let packages = offsets
+            .into_iter()
+            .zip(lengths)
+            .map(|(offset, length)| {
+                let blob = bytes
+                    .get(offset..offset.saturating_add(length))
+                    .ok_or(GoCoverageError::TruncatedCovdata(offset))?;
+                PackageMeta::from_bytes(blob)
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            hash,
+            mode,
+            granularity,
+            packages,
+        })
+    }
+}
+
+impl PackageMeta {
+    fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut r = SliceReader::new(bytes);
+        let _length = r.u32()?;
+        let name_index = r.u32()?;
+        let path_index = r.u32()?;
+        let module_path_index = r.u32()?;
+        let hash = r.hash()?;
+        r.bytes(4)?; // padding
+        let _number_of_files = r.u32()?;
+        let number_of_funcs = r.u32()?;
+        r.seek(PACKAGE_META_HEADER_SIZE);
+
+        let mut func_offsets = Vec::with_capacity((number_of_funcs as usize).min(bytes.len()));
+        for _ in 0..number_of_funcs {
+            func_offsets.push(r.u32()? as usize);
+        }
+        let strings = r.string_table()?;
+        let string = |index: u32| -> Result<String> {
+            strings
+                .get(index as usize)
+                .cloned()
+                .ok_or_else(|| GoCoverageError::InvalidStringIndex(index).into())
+        };
+
+        let mut funcs = Vec::with_capacity(func_offsets.len());
+        for offset in func_offsets {
+            r.seek(offset);
+            let number_of_units = r.uleb128()?;
+            let name = string(r.uleb128()?)?;
+            let src_file = string(r.uleb128()?)?;
+            let mut units = Vec::with_capacity((number_of_units as usize).min(bytes.len()));
+            for _ in 0..number_of_units {
+                units.push(CoverableUnit {
+                    start_line: r.uleb128()?,
+                    start_col: r.uleb128()?,
+                    end_line: r.uleb128()?,
+                    end_col: r.uleb128()?,
+                    number_of_statements: r.uleb128()?,
+                });
+            }
+            let is_literal = r.uleb128()? != 0;
+            funcs.push(FuncMeta {
+                name,
+                src_file,
+                units,
+                is_literal,
+            });
+        }
+
+        Ok(Self {
+            name: string(name_index)?,
+            path: string(path_index)?,
+            module_path: string(module_path_index)?,
+            hash,
+            funcs,
+        })
+    }
+}
+
+impl CounterFile {
+    /// Reads a `covcounters.*` file.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `GoCoverageError::InvalidCovdataMagic` or
+    /// `GoCoverageError::UnsupportedCovdataVersion` if the input isn't a supported counter data
+    /// file and with `GoCoverageError::TruncatedCovdata` if it ends early.
+    pub fn from_read<R: Read>(r: &mut R) -> Result<Self> {
+        let mut bytes = vec![];
+        r.read_to_end(&mut bytes)?;
+        let mut r = SliceReader::new(&bytes);
+
+        if r.bytes(4)? != COUNTER_FILE_MAGIC {
+            return Err(GoCoverageError::InvalidCovdataMagic.into());
+        }
+        let version = r.u32()?;
+        if version != COUNTER_FILE_VERSION {
+            return Err(GoCoverageError::UnsupportedCovdataVersion(version).into());
+        }
+        let meta_hash = r.hash()?;
+        let flavor = CounterFlavor::try_from(r.u8()?)?;
+        let big_endian = r.u8()? != 0;
+
+        // The footer of the last segment holds the number of segments
+        let footer_offset = bytes
+            .len()
+            .checked_sub(COUNTER_FILE_FOOTER_SIZE)
+            .filter(|offset| *offset >= COUNTER_FILE_HEADER_SIZE)
+            .ok_or(GoCoverageError::TruncatedCovdata(bytes.len()))?;
+        r.seek(footer_offset);
+        if r.bytes(4)? != COUNTER_FILE_MAGIC {
+            return Err(GoCoverageError::InvalidCovdataMagic.into());
+        }
+        r.bytes(4)?; // padding
+        let number_of_segments = r.u32()?;
+
+        r.seek(COUNTER_FILE_HEADER_SIZE);
+        let mut segments = vec![];
+        for _ in 0..number_of_segments {
+            segments.push(r.counter_segment(flavor, big_endian)?);
+            r.bytes(COUNTER_FILE_FOOTER_SIZE)?;
+        }
+
+        Ok(Self {
+            meta_hash,
+            flavor,
+            segments,
+        })
+    }
+}
+
+impl CovData {
+    /// Reads all `covmeta.*` and `covcounters.*` files in `dir`, other files are ignored.
+    ///
+    /// # Errors
+    ///
+    /// See [`MetaFile::from_read`] and [`CounterFile::from_read`].
+    pub fn from_dir<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let mut cov_data = Self::default();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            let Some(file_name) = path.file_name().and_then(|f| f.to_str()) else {
+                continue;
+            };
+            if file_name.starts_with(META_FILE_PREFIX) {
+                let meta_file = MetaFile::from_read(&mut BufReader::new(File::open(&path)?))?;
+                cov_data.meta_files.push(meta_file);
+            } else if file_name.starts_with(COUNTER_FILE_PREFIX) {
+                let counter_file = CounterFile::from_read(&mut BufReader::new(File::open(&path)?))?;
+                cov_data.counter_files.push(counter_file);
+            }
+        }
+        Ok(cov_data)
+    }
+}
+
+impl GoReport {
+    /// Reads all meta-data and counter data files in a `GOCOVERDIR` and converts them into a
+    /// report, the same way `go tool covdata textfmt` does. Other files are ignored.
+    ///
+    /// # Errors
+    ///
+    /// See [`GoReport::from_covdata`], [`MetaFile::from_read`] and [`CounterFile::from_read`].
+    pub fn from_covdata_dir<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let cov_data = CovData::from_dir(dir)?;
+        Self::from_covdata(&cov_data.meta_files, &cov_data.counter_files)
+    }
+
+    /// Converts meta-data and counter data into a report equivalent to the output of
+    /// `go tool covdata textfmt`: counters of all runs are merged, units without counters
+    /// have a count of 0, and blocks are sorted by package import path, file and position.
+    ///
+    /// # Errors
+    ///
+    /// Will return `GoCoverageError::MissingCovdataMetaFile` if a counter file has no matching
+    /// meta-data file, `GoCoverageError::InvalidCounterIndex` if it references a function that
+    /// doesn't exist, and `GoCoverageError::InconsistentCounterMode` if the meta-data files were
+    /// written with different or counter-less modes.
+    pub fn from_covdata(meta_files: &[MetaFile], counter_files: &[CounterFile]) -> Result<Self> {
+        let mode = match meta_files.first().map(MetaFile::mode) {
+            Some(CounterMode::Set) => GoProfileMode::Set,
+            Some(CounterMode::Count) => GoProfileMode::Count,
+            Some(CounterMode::Atomic) => GoProfileMode::Atomic,
+            Some(mode) => return Err(GoCoverageError::InconsistentCounterMode(mode as u8).into()),
+            None => return Err(GoCoverageError::EmptyCovdata.into()),
+        };
+        if let Some(meta_file) = meta_files.iter().find(|m| m.mode != meta_files[0].mode) {
+            return Err(GoCoverageError::InconsistentCounterMode(meta_file.mode as u8).into());
+        }
+
+        // Merged counters of all runs, keyed by meta file, package and function index
+        let mut counters: HashMap<(usize, u32, u32), Vec<u32>> = HashMap::new();
+        for counter_file in counter_files {
+            let meta_index = meta_files
+                .iter()
+                .position(|m| m.hash == counter_file.meta_hash)
+                .ok_or_else(|| {
+                    GoCoverageError::MissingCovdataMetaFile(hash_to_hex(&counter_file.meta_hash))
+                })?;
+            let meta_file = &meta_files[meta_index];
+
+            for func in counter_file.segments.iter().flat_map(|s| &s.funcs) {
+                meta_file.check_counter_index(func)?;
+
+                let merged = counters
+                    .entry((meta_index, func.package_index, func.func_index))
+                    .or_default();
+                if merged.len() < func.counters.len() {
+                    merged.resize(func.counters.len(), 0);
+                }
+                for (merged, &counter) in merged.iter_mut().zip(&func.counters) {
+                    *merged = match mode {
+                        GoProfileMode::Set => *merged | counter,
+                        GoProfileMode::Count | GoProfileMode::Atomic => {
+                            merged.saturating_add(counter)
+                        }
+                    };
+                }
+            }
+        }
+
+        // The same function may be part of several meta files, i.e. binaries
+        type Key<'a> = (&'a str, &'a str, &'a str, bool, CoverableUnit);
+        let mut units: HashMap<Key, u32> = HashMap::new();
+        for (meta_index, meta_file) in meta_files.iter().enumerate() {
+            for (package_index, package) in meta_file.packages.iter().enumerate() {
+                for (func_index, func) in package.funcs.iter().enumerate() {
+                    let func_counters =
+                        counters.get(&(meta_index, package_index as u32, func_index as u32));
+                    for (unit_index, unit) in func.units.iter().enumerate() {
+                        let counter_index = match meta_file.granularity {
+                            CounterGranularity::PerBlock => unit_index,
+                            CounterGranularity::PerFunc => 0,
+                        };
+                        let count = func_counters
+                            .and_then(|c| c.get(counter_index))
+                            .copied()
+                            .unwrap_or_default();
+                        let key = (
+                            package.path.as_str(),
+                            func.src_file.as_str(),
+                            func.name.as_str(),
+                            func.is_literal,
+                            *unit,
+                        );
+                        let merged = units.entry(key).or_default();
+                        *merged = match mode {
+                            GoProfileMode::Set => u32::from(*merged != 0 || count != 0),
+                            GoProfileMode::Count | GoProfileMode::Atomic => {
+                                merged.saturating_add(count)
+                            }
+                        };
+                    }
+                }
+            }
+        }
+
+        let mut units: Vec<_> = units.into_iter().collect();
+        units.sort_by_key(|((path, file, _, _, unit), _)| {
+            (
+                *path,
+                *file,
+                unit.start_line,
+                unit.end_line,
+                unit.start_col,
+                unit.end_col,
+                unit.number_of_statements,
+            )
+        });
+
+        let blocks = units
+            .into_iter()
+            .map(|((_, file, _, _, unit), count)| GoProfileBlock {
+                filename: file.to_string(),
+                start_line: unit.start_line,
+                start_col: unit.start_col,
+                end_line: unit.end_line,
+                end_col: unit.end_col,
+                number_of_statements: unit.number_of_statements,
+                count,
+            })
+            .collect();
+
+        Ok(Self { mode, blocks })
+    }
+}
+
+/// Little endian reader over an in-memory file, the binary formats are addressed by offsets.
+struct SliceReader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> SliceReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, offset: 0 }
+    }
+
+    fn seek(&mut self, offset: usize) {
+        self.offset = offset;
+    }
+
+    fn bytes(&mut self, length: usize) -> Result<&'a [u8]> {
+        let bytes = self
+            .offset
+            .checked_add(length)
+            .and_then(|end| self.bytes.get(self.offset..end))
+            .ok_or(GoCoverageError::TruncatedCovdata(self.offset))?;
+        self.offset += length;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.bytes(8)?.try_into().unwrap()))
+    }
+
+    fn hash(&mut self) -> Result<[u8; 16]> {
+        Ok(self.bytes(16)?.try_into().unwrap())
+    }
+
+    /// Reads an unsigned LEB128 value, truncated to 32 bits like Go's decoder does.
+    fn uleb128(&mut self) -> Result<u32> {
+        let mut value = 0u64;
+        let mut shift = 0;
+        loop {
+            let b = self.u8()?;
+            if shift < 64 {
+                value |= u64::from(b & 0x7F) << shift;
+            }
+            if b & 0x80 == 0 {
+                return Ok(value as u32);
+            }
+            shift += 7;
+        }
+    }
+
+    fn string_table(&mut self) -> Result<Vec<String>> {
+        let number_of_strings = self.uleb128()? as usize;
+        let mut strings = Vec::with_capacity(number_of_strings.min(self.bytes.len()));
+        for _ in 0..number_of_strings {
+            let length = self.uleb128()? as usize;
+            strings.push(String::from_utf8(self.bytes(length)?.to_vec())?);
+        }
+        Ok(strings)
+    }
+
+    fn counter_segment(
+        &mut self,
+        flavor: CounterFlavor,
+        big_endian: bool,
+    ) -> Result<CounterSegment> {
+        let number_of_funcs = self.u64()?;
+        let string_table_length = self.u32()? as usize;
+        let args_length = self.u32()? as usize;
+
+        let mut strings_reader = SliceReader::new(self.bytes(string_table_length)?);
+        let strings = strings_reader.string_table()?;
+        let string = |index: u32| -> Result<String> {
+            strings
+                .get(index as usize)
+                .cloned()
+                .ok_or_else(|| GoCoverageError::InvalidStringIndex(index).into())
+        };
+
+        let mut args_reader = SliceReader::new(self.bytes(args_length)?);
+        let number_of_args = args_reader.uleb128()?;
+        let mut args = Vec::with_capacity((number_of_args as usize).min(args_length));
+        for _ in 0..number_of_args {
+            args.push((
+                string(args_reader.uleb128()?)?,
+                string(args_reader.uleb128()?)?,
+            ));
+        }
+        // Go decodes the arguments into a map, so their order carries no meaning. They are
+        // sorted, which is also the order the writer uses
+        args.sort();
+
+        // Counters start at a 4 byte boundary
+        self.offset = self.offset.next_multiple_of(4);
+
+        let read_value = |r: &mut Self| -> Result<u32> {
+            match flavor {
+                CounterFlavor::Uleb128 => r.uleb128(),
+                CounterFlavor::Raw if big_endian => {
+                    Ok(u32::from_be_bytes(r.bytes(4)?.try_into().unwrap()))
+                }
+                CounterFlavor::Raw => r.u32(),
+            }
+        };
+
+        let mut funcs = vec![];
+        for _ in 0..number_of_funcs {
+            // Zero counts are padding, functions always have at least one counter
+            let mut number_of_counters = read_value(self)?;
+            while number_of_counters == 0 {
+                number_of_counters = read_value(self)?;
+            }
+            let package_index = read_value(self)?;
+            let func_index = read_value(self)?;
+            let mut counters =
+                Vec::with_capacity((number_of_counters as usize).min(self.bytes.len()));
+            for _ in 0..number_of_counters {
+                counters.push(read_value(self)?);
+            }
+            funcs.push(FuncCounters {
+                package_index,
+                func_index,
+                counters,
+            });
+        }
+
+        Ok(CounterSegment { args, funcs })
+    }
+}
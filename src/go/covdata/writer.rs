@@ -0,0 +1,391 @@
+use super::{
+    COUNTER_FILE_FOOTER_SIZE, COUNTER_FILE_HEADER_SIZE, COUNTER_FILE_MAGIC, COUNTER_FILE_PREFIX,
+    COUNTER_FILE_VERSION, CounterFile, CounterFlavor, CounterSegment, CovData, FuncCounters,
+    FuncMeta, META_FILE_HEADER_SIZE, META_FILE_MAGIC, META_FILE_PREFIX, META_FILE_VERSION,
+    MetaFile, PACKAGE_META_HEADER_SIZE, PackageMeta, Result, hash_to_hex,
+};
+
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+impl MetaFile {
+    /// Name of the file in a `GOCOVERDIR`, `covmeta.<hash>`.
+    pub fn file_name(&self) -> String {
+        format!("{META_FILE_PREFIX}{}", hash_to_hex(&self.hash))
+    }
+
+    /// Serializes the meta-data in the `covmeta.*` format. The package and file hashes are
+    /// written as they are, so they stay consistent with the counter files referencing them.
+    pub fn write_to<W: Write>(&self, w: &mut W) -> Result<()> {
+        let blobs: Vec<Vec<u8>> = self.packages.iter().map(PackageMeta::to_bytes).collect();
+
+        let mut string_table = StringTable::default();
+        string_table.index("");
+        let string_table = string_table.to_bytes();
+
+        let string_table_offset = META_FILE_HEADER_SIZE + 16 * blobs.len();
+        let mut offset = string_table_offset + string_table.len();
+        let mut offsets = Vec::with_capacity(blobs.len());
+        for blob in &blobs {
+            offsets.push(offset as u64);
+            offset += blob.len();
+        }
+
+        w.write_all(&META_FILE_MAGIC)?;
+        w.write_all(&META_FILE_VERSION.to_le_bytes())?;
+        w.write_all(&(offset as u64).to_le_bytes())?;
+        w.write_all(&(blobs.len() as u64).to_le_bytes())?;
+        w.write_all(&self.hash)?;
+        w.write_all(&u32::try_from(string_table_offset)?.to_le_bytes())?;
+        w.write_all(&u32::try_from(string_table.len())?.to_le_bytes())?;
+        w.write_all(&[self.mode as u8, self.granularity as u8])?;
+        w.write_all(&[0; 6])?;
+        for offset in offsets {
+            w.write_all(&offset.to_le_bytes())?;
+        }
+        for blob in &blobs {
+            w.write_all(&(blob.len() as u64).to_le_bytes())?;
+        }
+        w.write_all(&string_table)?;
+        for blob in &blobs {
+            w.write_all(blob)?;
+        }
+        Ok(())
+    }
+}
+
+impl PackageMeta {
+    fn to_bytes(&self) -> Vec<u8> {
+        // Same string order as Go's meta-data builder
+        let mut string_table = StringTable::default();
+        string_table.index("");
+        let path = string_table.index(&self.path);
+        let name = string_table.index(&self.name);
+        let module_path = string_table.index(&self.module_path);
+
+        let funcs: Vec<Vec<u8>> = self
+            .funcs
+            .iter()
+            .map(|func| func.to_bytes(&mut string_table))
+            .collect();
+        let number_of_files = self
+            .funcs
+            .iter()
+            .map(|func| &func.src_file)
+            .collect::<HashSet<_>>()
+            .len();
+        let string_table = string_table.to_bytes();
+
+        let mut offset = PACKAGE_META_HEADER_SIZE + 4 * funcs.len() + string_table.len();
+        let length = offset + funcs.iter().map(Vec::len).sum::<usize>();
+
+        let mut bytes = Vec::with_capacity(length);
+        for value in [length, name, path, module_path] {
+            bytes.extend_from_slice(&(value as u32).to_le_bytes());
+        }
+        bytes.extend_from_slice(&self.hash);
+        bytes.extend_from_slice(&[0; 4]);
+        bytes.extend_from_slice(&(number_of_files as u32).to_le_bytes());
+        bytes.extend_from_slice(&(funcs.len() as u32).to_le_bytes());
+        for func in &funcs {
+            bytes.extend_from_slice(&(offset as u32).to_le_bytes());
+            offset += func.len();
+        }
+        bytes.extend_from_slice(&string_table);
+        for func in &funcs {
+            bytes.extend_from_slice(func);
+        }
+        bytes
+    }
+}
+
+impl FuncMeta {
+    fn to_bytes<'a>(&'a self, string_table: &mut StringTable<'a>) -> Vec<u8> {
+        let mut bytes = vec![];
+        write_uleb128(&mut bytes, self.units.len() as u64);
+        write_uleb128(&mut bytes, string_table.index(&self.name) as u64);
+        write_uleb128(&mut bytes, string_table.index(&self.src_file) as u64);
+        for unit in &self.units {
+            for value in [
+                unit.start_line,
+                unit.start_col,
+                unit.end_line,
+                unit.end_col,
+                unit.number_of_statements,
+            ] {
+                write_uleb128(&mut bytes, value.into());
+            }
+        }
+        write_uleb128(&mut bytes, self.is_literal.into());
+        bytes
+    }
+}
+
+impl CounterFile {
+    /// Name of the file in a `GOCOVERDIR`, `covcounters.<meta hash>.<pid>.<nanotime>`.
+    pub fn file_name(&self, pid: u32, nanotime: u128) -> String {
+        format!(
+            "{COUNTER_FILE_PREFIX}{}.{pid}.{nanotime}",
+            hash_to_hex(&self.meta_hash)
+        )
+    }
+
+    /// Serializes the counters in the `covcounters.*` format, in little endian byte order.
+    /// A file without segments gets a single footer declaring 0 segments.
+    pub fn write_to<W: Write>(&self, w: &mut W) -> Result<()> {
+        w.write_all(&COUNTER_FILE_MAGIC)?;
+        w.write_all(&COUNTER_FILE_VERSION.to_le_bytes())?;
+        w.write_all(&self.meta_hash)?;
+        w.write_all(&[self.flavor as u8, 0])?;
+        w.write_all(&[0; 6])?;
+
+        if self.segments.is_empty() {
+            return write_counter_file_footer(w, 0);
+        }
+        let mut offset = COUNTER_FILE_HEADER_SIZE;
+        for (i, segment) in self.segments.iter().enumerate() {
+            let bytes = segment.to_bytes(self.flavor, offset)?;
+            w.write_all(&bytes)?;
+            offset += bytes.len() + COUNTER_FILE_FOOTER_SIZE;
+
+            // Every segment is followed by a footer with the number of segments so far
+            write_counter_file_footer(w, u32::try_from(i + 1)?)?;
+        }
+        Ok(())
+    }
+}
+
+fn write_counter_file_footer<W: Write>(w: &mut W, number_of_segments: u32) -> Result<()> {
+    w.write_all(&COUNTER_FILE_MAGIC)?;
+    w.write_all(&[0; 4])?;
+    w.write_all(&number_of_segments.to_le_bytes())?;
+    w.write_all(&[0; 4])?;
+    Ok(())
+}
+
+impl CounterSegment {
+    /// `offset` is the position of the segment in the file, counters are aligned to 4 bytes.
+    /// Functions without counters are skipped, like the Go runtime never emits them: a counter
+    /// count of 0 is read as padding.
+    fn to_bytes(&self, flavor: CounterFlavor, offset: usize) -> Result<Vec<u8>> {
+        let mut args: Vec<&(String, String)> = self.args.iter().collect();
+        args.sort();
+
+        let mut string_table = StringTable::default();
+        string_table.index("");
+        let mut args_bytes = vec![];
+        write_uleb128(&mut args_bytes, args.len() as u64);
+        for (key, value) in args {
+            write_uleb128(&mut args_bytes, string_table.index(key) as u64);
+            write_uleb128(&mut args_bytes, string_table.index(value) as u64);
+        }
+        let string_table = string_table.to_bytes();
+
+        let funcs: Vec<&FuncCounters> = self
+            .funcs
+            .iter()
+            .filter(|func| !func.counters.is_empty())
+            .collect();
+
+        let mut bytes = vec![];
+        bytes.extend_from_slice(&(funcs.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&u32::try_from(string_table.len())?.to_le_bytes());
+        bytes.extend_from_slice(&u32::try_from(args_bytes.len())?.to_le_bytes());
+        bytes.extend_from_slice(&string_table);
+        bytes.extend_from_slice(&args_bytes);
+        bytes.resize((offset + bytes.len()).next_multiple_of(4) - offset, 0);
+
+        let mut write_value = |value: u32| match flavor {
+            CounterFlavor::Uleb128 => write_uleb128(&mut bytes, value.into()),
+            CounterFlavor::Raw => bytes.extend_from_slice(&value.to_le_bytes()),
+        };
+        for func in funcs {
+            write_value(u32::try_from(func.counters.len())?);
+            write_value(func.package_index);
+            write_value(func.func_index);
+            for &counter in &func.counters {
+                write_value(counter);
+            }
+        }
+        Ok(bytes)
+    }
+}
+
+impl CovData {
+    /// Writes all meta-data and counter data files into `dir`, which is created if necessary.
+    /// Counter files are named after the current process and time, like the ones written by
+    /// `go tool covdata merge`.
+    pub fn write_to_dir<P: AsRef<Path>>(&self, dir: P) -> Result<()> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+
+        for meta_file in &self.meta_files {
+            let mut w = BufWriter::new(File::create(dir.join(meta_file.file_name()))?);
+            meta_file.write_to(&mut w)?;
+            w.flush()?;
+        }
+
+        let pid = std::process::id();
+        let nanotime = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default();
+        for (i, counter_file) in self.counter_files.iter().enumerate() {
+            // Keep names unique if there are several counter files for the same meta file
+            let file_name = counter_file.file_name(pid, nanotime + i as u128);
+            let mut w = BufWriter::new(File::create(dir.join(file_name))?);
+            counter_file.write_to(&mut w)?;
+            w.flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// String table with the same layout as Go's `internal/coverage/stringtab`.
+#[derive(Default)]
+struct StringTable<'a> {
+    strings: Vec<&'a str>,
+    indices: HashMap<&'a str, usize>,
+}
+
+impl<'a> StringTable<'a> {
+    fn index(&mut self, s: &'a str) -> usize {
+        *self.indices.entry(s).or_insert_with(|| {
+            self.strings.push(s);
+            self.strings.len() - 1
+        })
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        write_uleb128(&mut bytes, self.strings.len() as u64);
+        for s in &self.strings {
+            write_uleb128(&mut bytes, s.len() as u64);
+            bytes.extend_from_slice(s.as_bytes());
+        }
+        bytes
+    }
+}
+
+fn write_uleb128(bytes: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let b = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(b);
+            return;
+        }
+        bytes.push(b | 0x80);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::go::covdata::{CounterGranularity, CounterMode, CoverableUnit};
+
+    fn round_trip(counter_file: &CounterFile) -> CounterFile {
+        let mut bytes = vec![];
+        counter_file.write_to(&mut bytes).unwrap();
+        CounterFile::from_read(&mut bytes.as_slice()).unwrap()
+    }
+
+    #[test]
+    fn meta_file_round_trip() {
+        let funcs = vec![
+            FuncMeta::new(
+                "main".to_string(),
+                "example.com/calc/main.go".to_string(),
+                vec![
+                    CoverableUnit::new(5, 13, 8, 2, 2),
+                    CoverableUnit::new(9, 2, 9, 17, 1),
+                ],
+                false,
+            ),
+            FuncMeta::new(
+                "main.func1".to_string(),
+                "example.com/calc/main.go".to_string(),
+                vec![CoverableUnit::new(6, 10, 6, 30, 1)],
+                true,
+            ),
+        ];
+        let packages = vec![
+            PackageMeta::new(
+                "main".to_string(),
+                "example.com/calc".to_string(),
+                "example.com/calc".to_string(),
+                [1; 16],
+                funcs,
+            ),
+            PackageMeta::new(
+                "empty".to_string(),
+                "example.com/calc/empty".to_string(),
+                "example.com/calc".to_string(),
+                [2; 16],
+                vec![],
+            ),
+        ];
+        let meta_file = MetaFile::new(
+            [3; 16],
+            CounterMode::Count,
+            CounterGranularity::PerBlock,
+            packages,
+        );
+        let mut bytes = vec![];
+        meta_file.write_to(&mut bytes).unwrap();
+        assert_eq!(
+            MetaFile::from_read(&mut bytes.as_slice()).unwrap(),
+            meta_file
+        );
+    }
+
+    #[test]
+    fn counter_file_round_trip() {
+        for flavor in [CounterFlavor::Raw, CounterFlavor::Uleb128] {
+            let segments = vec![
+                CounterSegment::new(
+                    vec![("argc".to_string(), "1".to_string())],
+                    vec![
+                        FuncCounters::new(0, 0, vec![1, 0, 300]),
+                        FuncCounters::new(1, 2, vec![u32::MAX]),
+                    ],
+                ),
+                CounterSegment::new(vec![], vec![FuncCounters::new(0, 1, vec![3, 4])]),
+            ];
+            let counter_file = CounterFile::new([7; 16], flavor, segments);
+            assert_eq!(round_trip(&counter_file), counter_file);
+        }
+    }
+
+    #[test]
+    fn counter_file_without_segments_round_trip() {
+        for flavor in [CounterFlavor::Raw, CounterFlavor::Uleb128] {
+            let counter_file = CounterFile::new([7; 16], flavor, vec![]);
+            assert_eq!(round_trip(&counter_file), counter_file);
+        }
+    }
+
+    #[test]
+    fn functions_without_counters_are_skipped() {
+        for flavor in [CounterFlavor::Raw, CounterFlavor::Uleb128] {
+            let funcs = vec![
+                FuncCounters::new(0, 0, vec![]),
+                FuncCounters::new(0, 1, vec![3, 4]),
+            ];
+            let counter_file =
+                CounterFile::new([7; 16], flavor, vec![CounterSegment::new(vec![], funcs)]);
+            let expected = CounterFile::new(
+                [7; 16],
+                flavor,
+                vec![CounterSegment::new(
+                    vec![],
+                    vec![FuncCounters::new(0, 1, vec![3, 4])],
+                )],
+            );
+            assert_eq!(round_trip(&counter_file), expected);
+        }
+    }
+}
@@ -1,16 +1,75 @@
+use super::{Error, GoProfileMode};
+
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum GoCoverageError {
-    #[error("Invalid mode string")]
+    #[error("Missing mode line")]
     InvalidMode,
 
     #[error("Submitted invalid mode: {0}")]
     InvalidModeName(String),
 
-    #[error("Invalid line: {0:?}")]
-    InvalidLine(String),
+    #[error("Invalid line {line}: {reason}")]
+    InvalidLine { line: usize, reason: String },
+
+    #[error("Invalid block: {0}")]
+    InvalidBlock(String),
+
+    #[error("Incompatible modes {left} and {right}")]
+    IncompatibleModes {
+        left: GoProfileMode,
+        right: GoProfileMode,
+    },
 
     #[error("inconsistent NumStmt: changed from {from} to {to}")]
     InconsistentNumStmt { from: u32, to: u32 },
+
+    #[error("Invalid magic number, not a Go coverage data file")]
+    InvalidCovdataMagic,
+
+    #[error("Unsupported Go coverage data file version: {0}")]
+    UnsupportedCovdataVersion(u32),
+
+    #[error("Go coverage data is truncated at byte offset {0}")]
+    TruncatedCovdata(usize),
+
+    #[error("Invalid counter mode: {0}")]
+    InvalidCounterMode(u8),
+
+    #[error("Invalid counter granularity: {0}")]
+    InvalidCounterGranularity(u8),
+
+    #[error("Invalid counter flavor: {0}")]
+    InvalidCounterFlavor(u8),
+
+    #[error("Invalid string table index: {0}")]
+    InvalidStringIndex(u32),
+
+    #[error("No meta-data file with hash {0}")]
+    MissingCovdataMetaFile(String),
+
+    #[error("Counters for unknown function {func} of package {package}")]
+    InvalidCounterIndex { package: u32, func: u32 },
+
+    #[error("Inconsistent or unsupported counter mode: {0}")]
+    InconsistentCounterMode(u8),
+
+    #[error("No meta-data files")]
+    EmptyCovdata,
+
+    #[error("Source file not found: {0}")]
+    SourceNotFound(String),
+
+    #[error("No module directive in {0}")]
+    MissingModuleDirective(String),
+
+    #[error("Neither go.work nor go.mod found in {0}")]
+    MissingGoModule(String),
+
+    #[error("Can't resolve file name: {0}")]
+    UnresolvedFilename(String),
+
+    #[error("Can't merge input {index}: {source}")]
+    MergeInput { index: usize, source: Box<Error> },
 }
@@ -0,0 +1,212 @@
+//! Per-function statement coverage, the equivalent of `go tool cover -func`.
+
+use super::source::{GoFuncExtent, SourceProvider, find_funcs};
+use super::{GoProfileBlock, GoProfileMode, GoReport, Result};
+
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+#[cfg(feature = "serialization")]
+use serde::{Deserialize, Serialize};
+
+/// Statement coverage of every function of the files of a [`GoReport`].
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct GoFuncReport {
+    funcs: Vec<GoFuncCoverage>,
+}
+
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct GoFuncCoverage {
+    filename: String,
+    extent: GoFuncExtent,
+    covered_statements: u64,
+    total_statements: u64,
+}
+
+impl GoReport {
+    /// Assigns the profile blocks to the functions declared in the profiled files, the same
+    /// way `go tool cover -func` does: blocks of the same position are merged first, then every
+    /// block overlapping a function's extent counts towards that function.
+    ///
+    /// # Errors
+    ///
+    /// Will return the error of `sources` if a profiled file can't be found
+    pub fn func_coverage<S: SourceProvider>(&self, sources: &S) -> Result<GoFuncReport> {
+        let mut funcs = vec![];
+        for (filename, blocks) in self.blocks_by_file() {
+            let source = sources.source(filename)?;
+            for extent in find_funcs(&source) {
+                let (covered_statements, total_statements) = extent.coverage(&blocks);
+                funcs.push(GoFuncCoverage {
+                    filename: filename.to_string(),
+                    extent,
+                    covered_statements,
+                    total_statements,
+                });
+            }
+        }
+        Ok(GoFuncReport { funcs })
+    }
+
+    /// Blocks grouped by file and sorted by start position, with blocks of the same position
+    /// merged, like `golang.org/x/tools/cover.ParseProfiles` does.
+    pub(super) fn blocks_by_file(&self) -> BTreeMap<&str, Vec<GoProfileBlock>> {
+        let mut files: BTreeMap<&str, Vec<GoProfileBlock>> = BTreeMap::new();
+        for block in &self.blocks {
+            files
+                .entry(block.filename.as_str())
+                .or_default()
+                .push(block.clone());
+        }
+
+        for blocks in files.values_mut() {
+            blocks.sort_by_key(|b| (b.start_line, b.start_col));
+            let mut merged: Vec<GoProfileBlock> = Vec::with_capacity(blocks.len());
+            for block in blocks.drain(..) {
+                match merged.last_mut() {
+                    Some(last)
+                        if (last.start_line, last.start_col, last.end_line, last.end_col)
+                            == (
+                                block.start_line,
+                                block.start_col,
+                                block.end_line,
+                                block.end_col,
+                            ) =>
+                    {
+                        last.count = match self.mode {
+                            GoProfileMode::Set => last.count | block.count,
+                            GoProfileMode::Count | GoProfileMode::Atomic => {
+                                last.count.saturating_add(block.count)
+                            }
+                        };
+                    }
+                    _ => merged.push(block),
+                }
+            }
+            *blocks = merged;
+        }
+        files
+    }
+}
+
+impl GoFuncExtent {
+    /// Covered and total statements of the blocks within the function, `blocks` must be sorted
+    /// by start position.
+    fn coverage(&self, blocks: &[GoProfileBlock]) -> (u64, u64) {
+        let mut covered = 0;
+        let mut total = 0;
+        for block in blocks {
+            if (block.start_line, block.start_col) >= (self.end_line(), self.end_col()) {
+                // Past the end of the function
+                break;
+            }
+            if (block.end_line, block.end_col) <= (self.start_line(), self.start_col()) {
+                // Before the beginning of the function
+                continue;
+            }
+            total += u64::from(block.number_of_statements);
+            if block.count > 0 {
+                covered += u64::from(block.number_of_statements);
+            }
+        }
+        (covered, total)
+    }
+}
+
+impl GoFuncReport {
+    pub fn funcs(&self) -> &Vec<GoFuncCoverage> {
+        &self.funcs
+    }
+
+    pub fn funcs_mut(&mut self) -> &mut Vec<GoFuncCoverage> {
+        &mut self.funcs
+    }
+
+    pub fn covered_statements(&self) -> u64 {
+        self.funcs.iter().map(|f| f.covered_statements).sum()
+    }
+
+    pub fn total_statements(&self) -> u64 {
+        self.funcs.iter().map(|f| f.total_statements).sum()
+    }
+
+    /// Percentage of covered statements of all functions, the "total:" line of
+    /// `go tool cover -func`.
+    pub fn percent(&self) -> f64 {
+        percent(self.covered_statements(), self.total_statements())
+    }
+}
+
+impl GoFuncCoverage {
+    pub fn filename(&self) -> &String {
+        &self.filename
+    }
+
+    pub fn extent(&self) -> &GoFuncExtent {
+        &self.extent
+    }
+
+    pub fn name(&self) -> &String {
+        self.extent.name()
+    }
+
+    pub fn covered_statements(&self) -> u64 {
+        self.covered_statements
+    }
+
+    pub fn total_statements(&self) -> u64 {
+        self.total_statements
+    }
+
+    pub fn percent(&self) -> f64 {
+        percent(self.covered_statements, self.total_statements)
+    }
+}
+
+fn percent(covered: u64, total: u64) -> f64 {
+    100.0 * covered as f64 / total.max(1) as f64
+}
+
+/// Same output as `go tool cover -func`, including the alignment of its tab writer.
+impl Display for GoFuncReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut rows: Vec<[String; 3]> = self
+            .funcs
+            .iter()
+            .map(|func| {
+                [
+                    format!("{}:{}:", func.filename, func.extent.start_line()),
+                    func.name().clone(),
+                    format!("{:.1}%", func.percent()),
+                ]
+            })
+            .collect();
+        rows.push([
+            "total:".to_string(),
+            "(statements)".to_string(),
+            format!("{:.1}%", self.percent()),
+        ]);
+
+        // text/tabwriter with a tab width of 8, padding of 1 and tabs as padding characters
+        let column_width = |column: usize| {
+            let width = rows
+                .iter()
+                .map(|row| row[column].chars().count() + 1)
+                .max()
+                .unwrap_or_default();
+            width.next_multiple_of(8)
+        };
+        let widths = [column_width(0), column_width(1)];
+
+        for row in &rows {
+            for (cell, width) in row.iter().zip(widths) {
+                let tabs = (width - cell.chars().count()).div_ceil(8);
+                write!(f, "{cell}{}", "\t".repeat(tabs))?;
+            }
+            writeln!(f, "{}", row[2])?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,265 @@
+//! Annotated source HTML report, the equivalent of `go tool cover -html`.
+
+use super::source::SourceProvider;
+use super::{GoProfileBlock, GoProfileMode, GoReport, Result};
+
+use std::io::Write;
+
+impl GoReport {
+    /// Renders the report as a single HTML page with the annotated source of every profiled
+    /// file, the same way `go tool cover -html` does. Covered and uncovered blocks are colored,
+    /// code outside of blocks is not tracked. In `count` and `atomic` mode blocks are shaded by
+    /// their count relative to the highest count of the file.
+    ///
+    /// # Errors
+    ///
+    /// Will return the error of `sources` if a profiled file can't be found
+    pub fn write_html<S: SourceProvider, W: Write>(&self, sources: &S, w: &mut W) -> Result<()> {
+        let mut files = vec![];
+        for (filename, blocks) in self.blocks_by_file() {
+            let source = sources.source(filename)?;
+            let mut body = vec![];
+            write_annotated_source(&mut body, source.as_bytes(), &boundaries(&source, &blocks))?;
+            files.push((filename, body, percent_covered(&blocks)));
+        }
+
+        write!(w, "{HTML_HEAD}")?;
+        for i in 0..11 {
+            writeln!(w, "\t\t\t.cov{i} {{ color: {} }}", rgb(i))?;
+        }
+        write!(
+            w,
+            "\t\t</style>\n\t</head>\n\t<body>\n\t\t<div id=\"topbar\">\n\t\t\t<div id=\"nav\">\n\t\t\t\t<select id=\"files\">\n"
+        )?;
+        for (i, (filename, _, coverage)) in files.iter().enumerate() {
+            writeln!(
+                w,
+                "\t\t\t\t<option value=\"file{i}\">{} ({coverage:.1}%)</option>",
+                escape(filename)
+            )?;
+        }
+        write!(
+            w,
+            "\t\t\t\t</select>\n\t\t\t</div>\n\t\t\t<div id=\"legend\">\n\t\t\t\t<span>not tracked</span>\n"
+        )?;
+        if self.mode == GoProfileMode::Set {
+            write!(
+                w,
+                "\t\t\t\t<span class=\"cov0\">not covered</span>\n\t\t\t\t<span class=\"cov8\">covered</span>\n"
+            )?;
+        } else {
+            writeln!(w, "\t\t\t\t<span class=\"cov0\">no coverage</span>")?;
+            writeln!(w, "\t\t\t\t<span class=\"cov1\">low coverage</span>")?;
+            for i in 2..10 {
+                writeln!(w, "\t\t\t\t<span class=\"cov{i}\">*</span>")?;
+            }
+            writeln!(w, "\t\t\t\t<span class=\"cov10\">high coverage</span>")?;
+        }
+        write!(w, "\t\t\t</div>\n\t\t</div>\n\t\t<div id=\"content\">\n")?;
+        for (i, (_, body, _)) in files.iter().enumerate() {
+            write!(
+                w,
+                "\t\t<pre class=\"file\" id=\"file{i}\" style=\"display: none\">"
+            )?;
+            w.write_all(body)?;
+            writeln!(w, "</pre>")?;
+        }
+        write!(w, "\t\t</div>\n\t</body>\n{HTML_SCRIPT}")?;
+        Ok(())
+    }
+}
+
+/// Start or end of a block in the source, `norm` is the count normalized to `0.0..=1.0`.
+struct Boundary {
+    offset: usize,
+    start: bool,
+    count: u32,
+    norm: f64,
+}
+
+/// Byte offsets of the starts and ends of the blocks, like `cover.Profile.Boundaries`.
+fn boundaries(source: &str, blocks: &[GoProfileBlock]) -> Vec<Boundary> {
+    let max = blocks.iter().map(|b| b.count).max().unwrap_or_default();
+    let divisor = f64::from(max).ln();
+    let boundary = |offset: usize, start: bool, count: u32| {
+        let norm = if !start || count == 0 {
+            0.0
+        } else if max <= 1 {
+            // Set mode, always the same shade
+            0.8
+        } else {
+            f64::from(count).ln() / divisor
+        };
+        Boundary {
+            offset,
+            start,
+            count,
+            norm,
+        }
+    };
+
+    let src = source.as_bytes();
+    let mut boundaries = vec![];
+    // Go's implementation starts at column 2, keep the same offsets
+    let (mut line, mut col) = (1, 2);
+    let (mut si, mut bi) = (0, 0);
+    while si < src.len() && bi < blocks.len() {
+        let b = &blocks[bi];
+        if b.start_line == line && b.start_col == col {
+            boundaries.push(boundary(si, true, b.count));
+        }
+        if (b.end_line == line && b.end_col == col) || line > b.end_line {
+            boundaries.push(boundary(si, false, 0));
+            bi += 1;
+            // The next block may start at the same position
+            continue;
+        }
+        if src[si] == b'\n' {
+            line += 1;
+            col = 0;
+        }
+        col += 1;
+        si += 1;
+    }
+    // Stable, so boundaries at the same offset stay in their original order
+    boundaries.sort_by_key(|b| b.offset);
+    boundaries
+}
+
+fn write_annotated_source<W: Write>(w: &mut W, src: &[u8], boundaries: &[Boundary]) -> Result<()> {
+    let mut boundaries = boundaries.iter().peekable();
+    for (i, &b) in src.iter().enumerate() {
+        while let Some(boundary) = boundaries.next_if(|boundary| boundary.offset == i) {
+            if boundary.start {
+                let n = if boundary.count > 0 {
+                    (boundary.norm * 9.0).floor() as u32 + 1
+                } else {
+                    0
+                };
+                write!(w, "<span class=\"cov{n}\" title=\"{}\">", boundary.count)?;
+            } else {
+                write!(w, "</span>")?;
+            }
+        }
+        match b {
+            b'>' => w.write_all(b"&gt;")?,
+            b'<' => w.write_all(b"&lt;")?,
+            b'&' => w.write_all(b"&amp;")?,
+            b'\t' => w.write_all(b"        ")?,
+            _ => w.write_all(&[b])?,
+        }
+    }
+    Ok(())
+}
+
+fn percent_covered(blocks: &[GoProfileBlock]) -> f64 {
+    let total: u64 = blocks
+        .iter()
+        .map(|b| u64::from(b.number_of_statements))
+        .sum();
+    let covered: u64 = blocks
+        .iter()
+        .filter(|b| b.count > 0)
+        .map(|b| u64::from(b.number_of_statements))
+        .sum();
+    if total == 0 {
+        return 0.0;
+    }
+    covered as f64 / total as f64 * 100.0
+}
+
+/// Red for uncovered code, then a gradient from gray to green.
+fn rgb(n: i32) -> String {
+    if n == 0 {
+        return "rgb(192, 0, 0)".to_string();
+    }
+    let r = 128 - 12 * (n - 1);
+    let g = 128 + 12 * (n - 1);
+    let b = 128 + 3 * (n - 1);
+    format!("rgb({r}, {g}, {b})")
+}
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '&' => escaped.push_str("&amp;"),
+            '"' => escaped.push_str("&#34;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+const HTML_HEAD: &str = r#"
+<!DOCTYPE html>
+<html>
+	<head>
+		<meta http-equiv="Content-Type" content="text/html; charset=utf-8">
+		<title>Go Coverage Report</title>
+		<style>
+			body {
+				background: black;
+				color: rgb(80, 80, 80);
+			}
+			body, pre, #legend span {
+				font-family: Menlo, monospace;
+				font-weight: bold;
+			}
+			#topbar {
+				background: black;
+				position: fixed;
+				top: 0; left: 0; right: 0;
+				height: 42px;
+				border-bottom: 1px solid rgb(80, 80, 80);
+			}
+			#content {
+				margin-top: 50px;
+			}
+			#nav, #legend {
+				float: left;
+				margin-left: 10px;
+			}
+			#legend {
+				margin-top: 12px;
+			}
+			#nav {
+				margin-top: 10px;
+			}
+			#legend span {
+				margin: 0 5px;
+			}
+"#;
+
+const HTML_SCRIPT: &str = r#"	<script>
+	(function() {
+		var files = document.getElementById('files');
+		var visible;
+		files.addEventListener('change', onChange, false);
+		function select(part) {
+			if (visible)
+				visible.style.display = 'none';
+			visible = document.getElementById(part);
+			if (!visible)
+				return;
+			files.value = part;
+			visible.style.display = 'block';
+			location.hash = part;
+		}
+		function onChange() {
+			select(files.value);
+			window.scrollTo(0, 0);
+		}
+		if (location.hash != "") {
+			select(location.hash.substr(1));
+		}
+		if (!visible) {
+			select("file0");
+		}
+	})();
+	</script>
+</html>
+"#;
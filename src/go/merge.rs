@@ -0,0 +1,216 @@
+//! Merging of any number of profiles in a single pass.
+
+use super::Result;
+use super::reader::GoProfileReader;
+use super::{Error, GoCoverageError, GoModeMergePolicy, GoProfileBlock, GoProfileMode, GoReport};
+
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::io::BufRead;
+use std::sync::Mutex;
+use std::thread;
+
+impl GoReport {
+    /// Merges any number of reports in a single pass. The result is the same as converting all
+    /// reports to the mode chosen by `policy` and merging them with [`GoReport::try_merge_with`].
+    ///
+    /// # Errors
+    ///
+    /// Will return `GoCoverageError::MergeInput` with the index of the offending report, wrapping
+    /// `GoCoverageError::IncompatibleModes` or `GoCoverageError::InconsistentNumStmt`
+    pub fn try_merge_all<I: IntoIterator<Item = Self>>(
+        reports: I,
+        policy: GoModeMergePolicy,
+    ) -> Result<Self> {
+        let mut merger = BlockMerger::new(policy);
+        for (index, report) in reports.into_iter().enumerate() {
+            merger
+                .add(index, report.mode, report.blocks.into_iter().map(Ok))
+                .map_err(|e| merge_input_error(index, e))?;
+        }
+        Ok(merger.finish())
+    }
+
+    /// Reads and merges profiles on `threads` threads, without keeping more than the merged
+    /// blocks of every thread in memory. The readers are taken from `readers` one at a time, so
+    /// files can be opened lazily, an error in place of a reader is reported like a read error.
+    ///
+    /// # Errors
+    ///
+    /// Will return `GoCoverageError::MergeInput` with the index of the offending reader, wrapping
+    /// its read or parse error, `GoCoverageError::IncompatibleModes` or
+    /// `GoCoverageError::InconsistentNumStmt`. With several threads and more than one offending
+    /// reader, it's not defined which of them is reported.
+    pub fn try_merge_all_readers<R, I>(
+        readers: I,
+        policy: GoModeMergePolicy,
+        threads: usize,
+    ) -> Result<Self>
+    where
+        R: BufRead,
+        I: IntoIterator<Item = Result<R>>,
+        I::IntoIter: Send,
+    {
+        let readers = Mutex::new(readers.into_iter().enumerate());
+        let next_reader = || readers.lock().ok()?.next();
+
+        let merge_readers = || {
+            let mut merger = BlockMerger::new(policy);
+            while let Some((index, r)) = next_reader() {
+                let reader = r
+                    .and_then(GoProfileReader::new)
+                    .map_err(|e| merge_input_error(index, e))?;
+                let mode = reader.mode().clone();
+                merger
+                    .add(index, mode, reader)
+                    .map_err(|e| merge_input_error(index, e))?;
+            }
+            Ok(merger)
+        };
+
+        let mergers: Vec<Result<BlockMerger>> = thread::scope(|scope| {
+            let handles: Vec<_> = (1..threads).map(|_| scope.spawn(merge_readers)).collect();
+            let mut mergers = vec![merge_readers()];
+            for handle in handles {
+                mergers.push(
+                    handle
+                        .join()
+                        .unwrap_or_else(|panic| std::panic::resume_unwind(panic)),
+                );
+            }
+            mergers
+        });
+
+        let mut merged = BlockMerger::new(policy);
+        for merger in mergers {
+            merged.combine(merger?)?;
+        }
+        Ok(merged.finish())
+    }
+}
+
+fn merge_input_error(index: usize, error: Error) -> Error {
+    match error {
+        // Already attributed to an input
+        Error::Go(GoCoverageError::MergeInput { .. }) => error,
+        error => GoCoverageError::MergeInput {
+            index,
+            source: Box::new(error),
+        }
+        .into(),
+    }
+}
+
+type Key = (String, u32, u32, u32, u32);
+
+/// Counts are added up regardless of the mode and clamped to 1 at the end if the merged mode is
+/// `set`, which gives the same result as converting all inputs to the merged mode first.
+struct BlockMerger {
+    policy: GoModeMergePolicy,
+    /// Merged mode and the index of the first input with it
+    mode: Option<(GoProfileMode, usize)>,
+    /// Merged block and the index of the input it was first seen in
+    blocks: HashMap<Key, (GoProfileBlock, usize)>,
+}
+
+impl BlockMerger {
+    fn new(policy: GoModeMergePolicy) -> Self {
+        Self {
+            policy,
+            mode: None,
+            blocks: HashMap::new(),
+        }
+    }
+
+    fn add<B: Iterator<Item = Result<GoProfileBlock>>>(
+        &mut self,
+        index: usize,
+        mode: GoProfileMode,
+        blocks: B,
+    ) -> Result<()> {
+        self.merge_mode(mode, index)?;
+        for block in blocks {
+            self.merge_block(block?, index)?;
+        }
+        Ok(())
+    }
+
+    /// Adds the blocks merged by another thread.
+    fn combine(&mut self, other: Self) -> Result<()> {
+        if let Some((mode, index)) = other.mode {
+            self.merge_mode(mode, index)
+                .map_err(|e| merge_input_error(index, e))?;
+        }
+        for (block, index) in other.blocks.into_values() {
+            self.merge_block(block, index)
+                .map_err(|e| merge_input_error(index, e))?;
+        }
+        Ok(())
+    }
+
+    fn merge_mode(&mut self, mode: GoProfileMode, index: usize) -> Result<()> {
+        let Some((merged, _)) = &self.mode else {
+            self.mode = Some((mode, index));
+            return Ok(());
+        };
+        if *merged == mode {
+            return Ok(());
+        }
+        let mode = match self.policy {
+            GoModeMergePolicy::Error => {
+                return Err(GoCoverageError::IncompatibleModes {
+                    left: merged.clone(),
+                    right: mode,
+                }
+                .into());
+            }
+            GoModeMergePolicy::Upcast => merged.clone().max_by_precision(mode),
+            GoModeMergePolicy::Downcast => merged.clone().min_by_precision(mode),
+        };
+        if mode != *merged {
+            self.mode = Some((mode, index));
+        }
+        Ok(())
+    }
+
+    fn merge_block(&mut self, block: GoProfileBlock, index: usize) -> Result<()> {
+        let key = (
+            block.filename.clone(),
+            block.start_line,
+            block.start_col,
+            block.end_line,
+            block.end_col,
+        );
+        match self.blocks.entry(key) {
+            Entry::Occupied(mut occ) => {
+                let (existing, _) = occ.get_mut();
+                if existing.number_of_statements != block.number_of_statements {
+                    return Err(GoCoverageError::InconsistentNumStmt {
+                        from: existing.number_of_statements,
+                        to: block.number_of_statements,
+                    }
+                    .into());
+                }
+                existing.count = existing.count.saturating_add(block.count);
+            }
+            Entry::Vacant(vac) => {
+                vac.insert((block, index));
+            }
+        }
+        Ok(())
+    }
+
+    /// An empty report in `set` mode if there were no inputs.
+    fn finish(self) -> GoReport {
+        let mode = self.mode.map_or(GoProfileMode::Set, |(mode, _)| mode);
+        let mut blocks: Vec<GoProfileBlock> =
+            self.blocks.into_values().map(|(block, _)| block).collect();
+        if mode == GoProfileMode::Set {
+            for block in &mut blocks {
+                block.count = block.count.min(1);
+            }
+        }
+        blocks.sort();
+        GoReport { mode, blocks }
+    }
+}
@@ -1,13 +1,20 @@
-use std::{collections::HashMap, str::FromStr};
+use std::{collections::HashMap, fmt::Display, str::FromStr};
 
 pub(super) use super::{Error, Result};
 pub mod error;
 pub use error::GoCoverageError;
 
+pub mod convert;
+pub mod covdata;
+pub mod functions;
+pub mod html;
+pub mod merge;
 pub mod reader;
+pub mod resolver;
+pub mod source;
 pub mod writer;
 
-use lazy_regex::{lazy_regex, Lazy, Regex};
+use lazy_regex::{Lazy, Regex, lazy_regex};
 
 #[cfg(feature = "serialization")]
 use serde::{Deserialize, Serialize};
@@ -20,6 +27,19 @@ pub enum GoProfileMode {
     Atomic,
 }
 
+/// How [`GoReport::try_merge_with`] handles reports of different modes.
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub enum GoModeMergePolicy {
+    /// Fail with `GoCoverageError::IncompatibleModes`
+    #[default]
+    Error,
+    /// Convert both reports to the more precise mode, `set` < `count` < `atomic`
+    Upcast,
+    /// Convert both reports to the less precise mode
+    Downcast,
+}
+
 #[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
 pub struct GoReport {
@@ -40,66 +60,130 @@ pub struct GoProfileBlock {
 }
 
 impl GoReport {
+    /// Merges two reports of the same mode, see [`GoReport::try_merge_with`].
+    ///
     /// # Errors
     ///
-    /// Will return 'GoCoverageError::InconsistentNumStmt' if it encounters two coverages of the
-    /// same block with different numbers of statements
+    /// Will return 'GoCoverageError::IncompatibleModes' if the modes of the reports differ and
+    /// 'GoCoverageError::InconsistentNumStmt' if it encounters two coverages of the same block
+    /// with different numbers of statements
     pub fn try_merge(self, other: Self) -> Result<Self> {
-        let mode = [self.mode, other.mode]
-            .into_iter()
-            .find(|m| *m != GoProfileMode::Set)
-            .unwrap_or(GoProfileMode::Set);
-
-        type Key = (String, u32, u32, u32, u32);
-
-        let mut map: HashMap<Key, GoProfileBlock> =
-            HashMap::with_capacity(core::cmp::max(self.blocks.len(), other.blocks.len()));
-
-        let make_key = |b: &GoProfileBlock| {
-            (
-                b.filename.clone(),
-                b.start_line,
-                b.start_col,
-                b.end_line,
-                b.end_col,
-            )
+        self.try_merge_with(other, GoModeMergePolicy::Error)
+    }
+
+    /// Merges two reports, converting them to a common mode according to `policy` first. Counts
+    /// are ORed in `set` mode and added in `count` and `atomic` mode.
+    ///
+    /// # Errors
+    ///
+    /// Will return 'GoCoverageError::IncompatibleModes' if the modes of the reports differ and
+    /// `policy` is `GoModeMergePolicy::Error` and 'GoCoverageError::InconsistentNumStmt' if it
+    /// encounters two coverages of the same block with different numbers of statements
+    pub fn try_merge_with(self, other: Self, policy: GoModeMergePolicy) -> Result<Self> {
+        let mode = match policy {
+            _ if self.mode == other.mode => self.mode.clone(),
+            GoModeMergePolicy::Error => {
+                return Err(GoCoverageError::IncompatibleModes {
+                    left: self.mode,
+                    right: other.mode,
+                }
+                .into());
+            }
+            GoModeMergePolicy::Upcast => self.mode.clone().max_by_precision(other.mode.clone()),
+            GoModeMergePolicy::Downcast => self.mode.clone().min_by_precision(other.mode.clone()),
         };
+        let this = self.with_mode(mode.clone());
+        let other = other.with_mode(mode.clone());
 
-        // Insert all blocks from self
-        for b in self.blocks {
-            map.insert(make_key(&b), b);
-        }
+        let blocks = merge_blocks(&mode, this.blocks.into_iter().chain(other.blocks))?;
+        Ok(GoReport { mode, blocks })
+    }
 
-        // Merge non-zero blocks from other
-        for b in other.blocks.into_iter().filter(|b| b.count != 0) {
-            let k = make_key(&b);
-            match map.entry(k) {
-                std::collections::hash_map::Entry::Occupied(mut occ) => {
-                    let existing = occ.get_mut();
-                    if existing.number_of_statements != b.number_of_statements {
-                        return Err(Error::Go(GoCoverageError::InconsistentNumStmt {
-                            from: b.number_of_statements,
-                            to: existing.number_of_statements,
-                        }));
-                    }
-                    existing.count = match mode {
-                        GoProfileMode::Set => existing.count | b.count,
-                        GoProfileMode::Count | GoProfileMode::Atomic => {
-                            existing.count.saturating_add(b.count)
-                        }
-                    };
-                }
-                std::collections::hash_map::Entry::Vacant(vac) => {
-                    vac.insert(b);
+    /// Makes the blocks of every file disjoint, so the report is valid input for tools like
+    /// `go tool cover -html`. Blocks of the same position are merged like in
+    /// [`GoReport::try_merge`], which is all `go tool cover` itself does. Of overlapping or
+    /// nested blocks, which come from profiles of different versions of a file, the one with
+    /// the highest count is kept, the first in sort order on ties, so a covered block is never
+    /// dropped in favor of an uncovered one. Blocks are sorted afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Will return 'GoCoverageError::InconsistentNumStmt' if it encounters two coverages of the
+    /// same block with different numbers of statements
+    pub fn try_normalize(self) -> Result<Self> {
+        let blocks = merge_blocks(&self.mode, self.blocks.into_iter())?;
+
+        let mut disjoint: Vec<GoProfileBlock> = Vec::with_capacity(blocks.len());
+        for b in blocks {
+            // The last block is disjoint from the ones before it and starts before `b`, so `b`
+            // can only overlap the last block
+            if let Some(last) = disjoint.last_mut()
+                && last.filename == b.filename
+                && (b.start_line, b.start_col) < (last.end_line, last.end_col)
+            {
+                if b.count > last.count {
+                    *last = b;
                 }
+                continue;
             }
+            disjoint.push(b);
         }
 
-        let mut blocks: Vec<_> = map.into_values().collect();
-        blocks.sort();
+        Ok(GoReport {
+            mode: self.mode,
+            blocks: disjoint,
+        })
+    }
+}
 
-        Ok(GoReport { mode, blocks })
+/// Merges blocks of the same position, the result is sorted.
+fn merge_blocks<I: Iterator<Item = GoProfileBlock>>(
+    mode: &GoProfileMode,
+    blocks: I,
+) -> Result<Vec<GoProfileBlock>> {
+    type Key = (String, u32, u32, u32, u32);
+
+    let mut map: HashMap<Key, GoProfileBlock> = HashMap::with_capacity(blocks.size_hint().0);
+
+    let make_key = |b: &GoProfileBlock| {
+        (
+            b.filename.clone(),
+            b.start_line,
+            b.start_col,
+            b.end_line,
+            b.end_col,
+        )
+    };
+
+    // Every block is kept, including the ones with a count of 0, and every pair of blocks of
+    // the same position is checked for consistency
+    for b in blocks {
+        let k = make_key(&b);
+        match map.entry(k) {
+            std::collections::hash_map::Entry::Occupied(mut occ) => {
+                let existing = occ.get_mut();
+                if existing.number_of_statements != b.number_of_statements {
+                    return Err(Error::Go(GoCoverageError::InconsistentNumStmt {
+                        from: existing.number_of_statements,
+                        to: b.number_of_statements,
+                    }));
+                }
+                existing.count = match mode {
+                    GoProfileMode::Set => existing.count | b.count,
+                    GoProfileMode::Count | GoProfileMode::Atomic => {
+                        existing.count.saturating_add(b.count)
+                    }
+                };
+            }
+            std::collections::hash_map::Entry::Vacant(vac) => {
+                vac.insert(b);
+            }
+        }
     }
+
+    let mut blocks: Vec<_> = map.into_values().collect();
+    blocks.sort();
+    Ok(blocks)
 }
 
 impl FromStr for GoProfileMode {
@@ -123,30 +207,87 @@ impl GoProfileMode {
             GoProfileMode::Atomic => "atomic",
         }
     }
+
+    /// `atomic` counts the same as `count`, but also under concurrency, so it's the most precise.
+    fn precision(&self) -> u8 {
+        match self {
+            GoProfileMode::Set => 0,
+            GoProfileMode::Count => 1,
+            GoProfileMode::Atomic => 2,
+        }
+    }
+
+    pub(super) fn max_by_precision(self, other: Self) -> Self {
+        if other.precision() > self.precision() {
+            other
+        } else {
+            self
+        }
+    }
+
+    pub(super) fn min_by_precision(self, other: Self) -> Self {
+        if other.precision() < self.precision() {
+            other
+        } else {
+            self
+        }
+    }
+}
+
+impl Display for GoProfileMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 static GO_PROFILE_BLOCK_RE: Lazy<Regex, fn() -> Regex> =
     lazy_regex!(r#"^(.+):([0-9]+)\.([0-9]+),([0-9]+)\.([0-9]+) ([0-9]+) ([0-9]+)$"#);
 
+/// Parses a single block line without line context, errors are `GoCoverageError::InvalidBlock`.
 impl FromStr for GoProfileBlock {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self> {
-        let captures = GO_PROFILE_BLOCK_RE
-            .captures(s)
-            .ok_or(error::GoCoverageError::InvalidLine(s.to_string()))?;
+        Self::parse(s).map_err(|reason| GoCoverageError::InvalidBlock(reason).into())
+    }
+}
 
-        let (_, [filename, start_line, start_col, end_line, end_col, number_of_statements, count]) =
-            captures.extract();
+impl GoProfileBlock {
+    /// Parses a line of a profile, the error is the reason why it's invalid.
+    pub(super) fn parse(s: &str) -> std::result::Result<Self, String> {
+        let captures = GO_PROFILE_BLOCK_RE.captures(s).ok_or_else(|| {
+            format!(
+                "expected \"file:startLine.startCol,endLine.endCol numberOfStatements count\", got {s:?}"
+            )
+        })?;
+
+        let (
+            _,
+            [
+                filename,
+                start_line,
+                start_col,
+                end_line,
+                end_col,
+                number_of_statements,
+                count,
+            ],
+        ) = captures.extract();
+
+        let number = |name: &str, value: &str| {
+            value
+                .parse::<u32>()
+                .map_err(|e| format!("invalid {name} {value:?}: {e}"))
+        };
 
         let block = Self {
             filename: filename.to_string(),
-            start_line: start_line.parse()?,
-            start_col: start_col.parse()?,
-            end_line: end_line.parse()?,
-            end_col: end_col.parse()?,
-            number_of_statements: number_of_statements.parse()?,
-            count: count.parse()?,
+            start_line: number("start line", start_line)?,
+            start_col: number("start column", start_col)?,
+            end_line: number("end line", end_line)?,
+            end_col: number("end column", end_col)?,
+            number_of_statements: number("number of statements", number_of_statements)?,
+            count: number("count", count)?,
         };
 
         Ok(block)
@@ -169,6 +310,20 @@ impl GoReport {
         &mut self.mode
     }
 
+    /// Converts the report to another mode. Converting to `set` clamps counts to 1. Converting
+    /// `set` to `count` or `atomic` keeps the 0 and 1 values, so a covered block gets the lowest
+    /// count it can have had. `count` and `atomic` only differ in how they were collected, their
+    /// counts are kept as they are.
+    pub fn with_mode(mut self, mode: GoProfileMode) -> Self {
+        if mode == GoProfileMode::Set {
+            for block in &mut self.blocks {
+                block.count = block.count.min(1);
+            }
+        }
+        self.mode = mode;
+        self
+    }
+
     pub fn profile_blocks(&self) -> &Vec<GoProfileBlock> {
         &self.blocks
     }
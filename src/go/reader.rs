@@ -1,40 +1,218 @@
 use super::Result;
 use crate::go::{GoCoverageError, GoProfileBlock, GoProfileMode, GoReport};
 
-use lazy_regex::{lazy_regex, Lazy, Regex};
-use std::{io::BufRead, str::FromStr};
+use std::{fmt::Display, io::BufRead, str::FromStr};
 
-static FIRST_LINE_RE: Lazy<Regex, fn() -> Regex> = lazy_regex!("^mode: (.*)\n$");
+#[cfg(feature = "serialization")]
+use serde::{Deserialize, Serialize};
+
+const MISSING_MODE: &str = "expected \"mode: set|count|atomic\" before the first block";
+
+/// Invalid line of a profile, collected by [`GoReport::from_buf_read_lenient`].
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct GoParseDiagnostic {
+    /// 1-based line number
+    line: usize,
+    reason: String,
+}
+
+impl GoParseDiagnostic {
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn reason(&self) -> &String {
+        &self.reason
+    }
+}
+
+impl Display for GoParseDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.reason)
+    }
+}
 
 impl GoReport {
+    /// Reads a profile in the text format of `go test -coverprofile`. Blank lines and CRLF line
+    /// endings are accepted, as are concatenated profiles repeating the `mode:` header.
+    ///
+    /// # Errors
+    ///
+    /// Will return `GoCoverageError::InvalidLine` with the 1-based line number and the reason
+    /// for the first invalid line, including a header with a mode other than the first one,
+    /// `GoCoverageError::InvalidMode` for an empty profile and the error of `r` if reading fails
     pub fn from_buf_read<R: BufRead>(r: &mut R) -> Result<Self> {
-        // 12 since normally we expect first line to be not longer than "mode: atomic" + newline
-        let mut first_line = String::with_capacity(13);
-        r.read_line(&mut first_line)?;
-        let captures = FIRST_LINE_RE
-            .captures(&first_line)
-            .ok_or(GoCoverageError::InvalidMode)?;
-
-        let (_, [mode_str]) = captures.extract();
-
-        let mode = GoProfileMode::from_str(mode_str)?;
-
-        let mut profile_blocks = vec![];
-        let mut current_line = String::new();
-        while r.read_line(&mut current_line).is_ok() {
-            if current_line.is_empty() {
-                break; // EOF
-            }
-            let parsed_profile_block = GoProfileBlock::from_str(current_line.trim_end())?;
-            profile_blocks.push(parsed_profile_block);
-            current_line.clear();
+        let reader = GoProfileReader::new(r)?;
+        let mode = reader.mode().clone();
+        let blocks = reader.collect::<Result<Vec<_>>>()?;
+        Ok(Self { mode, blocks })
+    }
+
+    /// Like [`GoReport::from_buf_read`], but skips invalid lines and returns a diagnostic for
+    /// each of them instead. The mode of the first header applies to all blocks, `set` if there
+    /// is none.
+    ///
+    /// # Errors
+    ///
+    /// Will return the error of `r` if reading fails
+    pub fn from_buf_read_lenient<R: BufRead>(r: &mut R) -> Result<(Self, Vec<GoParseDiagnostic>)> {
+        let mut diagnostics = vec![];
+        let mut parser = LineParser::default();
+        let mut blocks = vec![];
+        let mut collect = |line, reason| {
+            diagnostics.push(GoParseDiagnostic { line, reason });
+            Ok(())
+        };
+        while let Some(block) = parser.next_block(r, &mut collect)? {
+            blocks.push(block);
+        }
+        if parser.mode.is_none() && diagnostics.is_empty() {
+            diagnostics.push(GoParseDiagnostic {
+                line: 1,
+                reason: MISSING_MODE.to_string(),
+            });
         }
+        let mode = parser.mode.unwrap_or(GoProfileMode::Set);
+        Ok((Self { mode, blocks }, diagnostics))
+    }
+}
+
+/// Iterator over the blocks of a profile that reads one line at a time, so profiles of any size
+/// can be filtered or transformed with bounded memory. Invalid lines are rejected the same way
+/// [`GoReport::from_buf_read`] does, iteration ends after the first error.
+pub struct GoProfileReader<R: BufRead> {
+    r: R,
+    parser: LineParser,
+    mode: GoProfileMode,
+    first_block: Option<GoProfileBlock>,
+    done: bool,
+}
 
-        let report = Self {
+impl<R: BufRead> GoProfileReader<R> {
+    /// Reads the profile up to its first block to find out its mode.
+    ///
+    /// # Errors
+    ///
+    /// Will return `GoCoverageError::InvalidLine` if the profile doesn't start with a valid mode
+    /// header, `GoCoverageError::InvalidMode` for an empty profile and the error of `r` if
+    /// reading fails
+    pub fn new(mut r: R) -> Result<Self> {
+        let mut parser = LineParser::default();
+        let first_block = parser.next_block(&mut r, &mut reject_line)?;
+        let mode = parser.mode.clone().ok_or(GoCoverageError::InvalidMode)?;
+        Ok(Self {
+            r,
+            parser,
             mode,
-            blocks: profile_blocks,
-        };
+            first_block,
+            done: false,
+        })
+    }
 
-        Ok(report)
+    pub fn mode(&self) -> &GoProfileMode {
+        &self.mode
+    }
+
+    pub fn into_inner(self) -> R {
+        self.r
+    }
+}
+
+impl<R: BufRead> Iterator for GoProfileReader<R> {
+    type Item = Result<GoProfileBlock>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(block) = self.first_block.take() {
+            return Some(Ok(block));
+        }
+        if self.done {
+            return None;
+        }
+        match self.parser.next_block(&mut self.r, &mut reject_line) {
+            Ok(Some(block)) => Some(Ok(block)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+fn reject_line(line: usize, reason: String) -> Result<()> {
+    Err(GoCoverageError::InvalidLine { line, reason }.into())
+}
+
+/// State of reading a profile line by line.
+#[derive(Default)]
+struct LineParser {
+    mode: Option<GoProfileMode>,
+    missing_mode_reported: bool,
+    line_number: usize,
+    buf: Vec<u8>,
+}
+
+impl LineParser {
+    /// Reads up to the next block, `None` at the end of the profile. `invalid_line` is called
+    /// with the line number and reason of every invalid line, its error is returned.
+    fn next_block<R, F>(
+        &mut self,
+        r: &mut R,
+        invalid_line: &mut F,
+    ) -> Result<Option<GoProfileBlock>>
+    where
+        R: BufRead,
+        F: FnMut(usize, String) -> Result<()>,
+    {
+        loop {
+            self.buf.clear();
+            if r.read_until(b'\n', &mut self.buf)? == 0 {
+                return Ok(None); // EOF
+            }
+            self.line_number += 1;
+            let line_number = self.line_number;
+
+            let Ok(line) = std::str::from_utf8(&self.buf) else {
+                invalid_line(line_number, "invalid UTF-8".to_string())?;
+                continue;
+            };
+            let line = line.trim_end_matches('\n').trim_end_matches('\r');
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix("mode:") {
+                let name = name.trim();
+                match (GoProfileMode::from_str(name), &self.mode) {
+                    (Err(_), _) => invalid_line(line_number, format!("invalid mode {name:?}"))?,
+                    (Ok(header_mode), None) => self.mode = Some(header_mode),
+                    (Ok(header_mode), Some(first_mode)) if header_mode != *first_mode => {
+                        invalid_line(
+                            line_number,
+                            format!(
+                                "mode {} differs from mode {} of the first header",
+                                header_mode.as_str(),
+                                first_mode.as_str()
+                            ),
+                        )?;
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+
+            if self.mode.is_none() && !self.missing_mode_reported {
+                invalid_line(line_number, MISSING_MODE.to_string())?;
+                self.missing_mode_reported = true;
+            }
+            match GoProfileBlock::parse(line) {
+                Ok(block) => return Ok(Some(block)),
+                Err(reason) => invalid_line(line_number, reason)?,
+            }
+        }
     }
 }
@@ -0,0 +1,291 @@
+//! Mapping between the import path based file names of Go coverage profiles, e.g.
+//! `github.com/org/repo/pkg/file.go`, and files on disk.
+
+use super::source::SourceProvider;
+use super::{GoCoverageError, GoReport, Result};
+
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Resolves profile file names using the modules of a `go.mod` or `go.work` file, their local
+/// `replace` directives and the `vendor` directory.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct ModuleResolver {
+    /// Module path and directory of the main modules and local replacements
+    modules: Vec<(String, PathBuf)>,
+    vendor_dirs: Vec<PathBuf>,
+}
+
+impl ModuleResolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads `<dir>/go.work` if it exists, otherwise `<dir>/go.mod`. For a workspace all used
+    /// modules are added, with the `replace` directives of the workspace and of every module.
+    ///
+    /// # Errors
+    ///
+    /// Will return `GoCoverageError::MissingGoModule` if there is neither a `go.work` nor a
+    /// `go.mod` file in `dir` and `GoCoverageError::MissingModuleDirective` if a `go.mod` file
+    /// has no module directive
+    pub fn from_dir<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let dir = dir.as_ref();
+        let mut resolver = Self::new();
+
+        let go_work = dir.join("go.work");
+        if go_work.is_file() {
+            let directives = parse_directives(&fs::read_to_string(&go_work)?);
+            for (verb, args) in &directives {
+                if verb == "use"
+                    && let Some(module_dir) = args.first()
+                {
+                    resolver.add_go_mod(dir.join(module_dir))?;
+                }
+            }
+            resolver.add_replacements(dir, &directives);
+        } else if dir.join("go.mod").is_file() {
+            resolver.add_go_mod(dir)?;
+        } else {
+            return Err(GoCoverageError::MissingGoModule(dir.display().to_string()).into());
+        }
+
+        let vendor_dir = dir.join("vendor");
+        if vendor_dir.is_dir() {
+            resolver.vendor_dirs.push(vendor_dir);
+        }
+        Ok(resolver)
+    }
+
+    /// Adds the module of `<dir>/go.mod` and its local replacements.
+    ///
+    /// # Errors
+    ///
+    /// Will return `GoCoverageError::MissingModuleDirective` if `go.mod` has no module directive
+    pub fn add_go_mod<P: AsRef<Path>>(&mut self, dir: P) -> Result<()> {
+        let dir = dir.as_ref();
+        let (module_path, directives) = read_go_mod(&dir.join("go.mod"))?;
+        self.add_module(module_path, dir);
+        self.add_replacements(dir, &directives);
+        Ok(())
+    }
+
+    /// Adds a module whose sources are in `dir`.
+    pub fn add_module<P: AsRef<Path>>(&mut self, module_path: String, dir: P) {
+        self.modules.push((module_path, normalize(dir.as_ref())));
+    }
+
+    /// Adds a `vendor` directory, in which packages are looked up by their import path.
+    pub fn add_vendor_dir<P: AsRef<Path>>(&mut self, dir: P) {
+        self.vendor_dirs.push(dir.as_ref().to_path_buf());
+    }
+
+    pub fn modules(&self) -> &Vec<(String, PathBuf)> {
+        &self.modules
+    }
+
+    pub fn vendor_dirs(&self) -> &Vec<PathBuf> {
+        &self.vendor_dirs
+    }
+
+    /// Only replacements by local directories can be resolved, replacements by other module
+    /// versions live in the module cache.
+    fn add_replacements(&mut self, dir: &Path, directives: &[Directive]) {
+        for (verb, args) in directives {
+            if verb != "replace" {
+                continue;
+            }
+            let Some(arrow) = args.iter().position(|arg| arg == "=>") else {
+                continue;
+            };
+            if let (Some(module_path), Some(target)) = (args.first(), args.get(arrow + 1))
+                && is_local_path(target)
+            {
+                self.add_module(module_path.clone(), dir.join(target));
+            }
+        }
+    }
+
+    /// Local path of a profile file name, `None` if it isn't part of a known module or vendored.
+    /// Modules are matched by their longest path prefix. File names of packages outside of any
+    /// module, which are absolute paths or prefixed with `_`, are returned as they are.
+    pub fn resolve(&self, filename: &str) -> Option<PathBuf> {
+        let module = self
+            .modules
+            .iter()
+            .filter_map(|(module_path, dir)| {
+                let relative = filename
+                    .strip_prefix(module_path.as_str())?
+                    .strip_prefix('/')?;
+                Some((module_path.len(), dir.join(relative)))
+            })
+            .max_by_key(|(length, _)| *length);
+        if let Some((_, path)) = module {
+            return Some(path);
+        }
+
+        let vendored = self
+            .vendor_dirs
+            .iter()
+            .map(|vendor_dir| vendor_dir.join(filename))
+            .find(|path| path.is_file());
+        if vendored.is_some() {
+            return vendored;
+        }
+
+        let path = Path::new(filename.strip_prefix('_').unwrap_or(filename));
+        path.is_absolute().then(|| path.to_path_buf())
+    }
+
+    /// Profile file name of a local path, the inverse of [`ModuleResolver::resolve`].
+    pub fn import_path<P: AsRef<Path>>(&self, path: P) -> Option<String> {
+        let path = normalize(path.as_ref());
+        // Vendor directories are inside of the main module, so they take precedence
+        let vendored = self
+            .vendor_dirs
+            .iter()
+            .find_map(|vendor_dir| path.strip_prefix(normalize(vendor_dir)).ok());
+        if let Some(relative) = vendored {
+            return Some(slash_path(relative));
+        }
+
+        self.modules
+            .iter()
+            .filter_map(|(module_path, dir)| {
+                let relative = path.strip_prefix(dir).ok()?;
+                Some((dir.components().count(), module_path, relative))
+            })
+            .max_by_key(|(depth, _, _)| *depth)
+            .map(|(_, module_path, relative)| format!("{module_path}/{}", slash_path(relative)))
+    }
+}
+
+impl SourceProvider for ModuleResolver {
+    fn source(&self, filename: &str) -> Result<String> {
+        let path = self
+            .resolve(filename)
+            .ok_or_else(|| GoCoverageError::SourceNotFound(filename.to_string()))?;
+        fs::read_to_string(path)
+            .map_err(|_| GoCoverageError::SourceNotFound(filename.to_string()).into())
+    }
+}
+
+impl GoReport {
+    /// Replaces the import path based file names of all blocks with local paths.
+    ///
+    /// # Errors
+    ///
+    /// Will return `GoCoverageError::UnresolvedFilename` if a file name can't be resolved
+    pub fn with_local_filenames(mut self, resolver: &ModuleResolver) -> Result<Self> {
+        for block in &mut self.blocks {
+            let path = resolver
+                .resolve(&block.filename)
+                .ok_or_else(|| GoCoverageError::UnresolvedFilename(block.filename.clone()))?;
+            block.filename = path.to_string_lossy().into_owned();
+        }
+        Ok(self)
+    }
+
+    /// Replaces local paths with import path based file names, the inverse of
+    /// [`GoReport::with_local_filenames`].
+    ///
+    /// # Errors
+    ///
+    /// Will return `GoCoverageError::UnresolvedFilename` if a path isn't part of a known module
+    pub fn with_import_filenames(mut self, resolver: &ModuleResolver) -> Result<Self> {
+        for block in &mut self.blocks {
+            block.filename = resolver
+                .import_path(&block.filename)
+                .ok_or_else(|| GoCoverageError::UnresolvedFilename(block.filename.clone()))?;
+        }
+        Ok(self)
+    }
+}
+
+fn is_local_path(path: &str) -> bool {
+    path.starts_with("./") || path.starts_with("../") || Path::new(path).is_absolute()
+}
+
+/// Removes `.` and `..` components without touching the file system.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push("..");
+                }
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+fn slash_path(path: &Path) -> String {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Verb and arguments of a directive, e.g. `replace` and `[old, =>, ../new]`.
+pub(super) type Directive = (String, Vec<String>);
+
+/// Reads a `go.mod` file, returns the module path of its `module` directive and all its
+/// directives.
+///
+/// # Errors
+///
+/// Will return `GoCoverageError::MissingModuleDirective` if there is no module directive
+pub(super) fn read_go_mod(go_mod: &Path) -> Result<(String, Vec<Directive>)> {
+    let directives = parse_directives(&fs::read_to_string(go_mod)?);
+    let module_path = directives
+        .iter()
+        .find(|(verb, _)| verb == "module")
+        .and_then(|(_, args)| args.first())
+        .cloned()
+        .ok_or_else(|| GoCoverageError::MissingModuleDirective(go_mod.display().to_string()))?;
+    Ok((module_path, directives))
+}
+
+/// Directives of a `go.mod` or `go.work` file as verb and arguments, with blocks like
+/// `replace ( ... )` expanded into one directive per line.
+fn parse_directives(contents: &str) -> Vec<Directive> {
+    let mut directives = vec![];
+    let mut block_verb: Option<String> = None;
+    for line in contents.lines() {
+        let tokens = tokenize_line(line);
+        match (&block_verb, tokens.as_slice()) {
+            (_, []) => {}
+            (Some(_), [close]) if close == ")" => block_verb = None,
+            (Some(verb), _) => directives.push((verb.clone(), tokens)),
+            (None, [verb, open]) if open == "(" => block_verb = Some(verb.clone()),
+            (None, [verb, args @ ..]) => directives.push((verb.clone(), args.to_vec())),
+        }
+    }
+    directives
+}
+
+/// Splits a line into whitespace separated tokens, unquoting quoted strings and dropping
+/// comments.
+fn tokenize_line(line: &str) -> Vec<String> {
+    let mut tokens = vec![];
+    let mut rest = line.trim_start();
+    while !rest.is_empty() && !rest.starts_with("//") {
+        let (token, remainder) = match rest.chars().next() {
+            Some(quote @ ('"' | '`')) => {
+                let end = rest[1..].find(quote).map_or(rest.len(), |end| end + 1);
+                (&rest[1..end], rest.get(end + 1..).unwrap_or_default())
+            }
+            _ => {
+                let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                (&rest[..end], &rest[end..])
+            }
+        };
+        tokens.push(token.to_string());
+        rest = remainder.trim_start();
+    }
+    tokens
+}
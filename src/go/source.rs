@@ -0,0 +1,429 @@
+//! Access to the Go sources referenced by [`super::GoProfileBlock::filename`], and a minimal
+//! scanner for the function declarations in them.
+
+use super::resolver::read_go_mod;
+use super::{GoCoverageError, Result};
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "serialization")]
+use serde::{Deserialize, Serialize};
+
+/// Looks up the contents of a Go source file by the file name used in coverage profiles, e.g.
+/// `example.com/module/pkg/file.go`.
+pub trait SourceProvider {
+    /// # Errors
+    ///
+    /// Should return `GoCoverageError::SourceNotFound` if there is no such file
+    fn source(&self, filename: &str) -> Result<String>;
+}
+
+/// Sources in memory, keyed by the file name used in coverage profiles.
+impl SourceProvider for HashMap<String, String> {
+    fn source(&self, filename: &str) -> Result<String> {
+        self.get(filename)
+            .cloned()
+            .ok_or_else(|| GoCoverageError::SourceNotFound(filename.to_string()).into())
+    }
+}
+
+/// Sources of a single module checked out at `root`: file names starting with the module path
+/// are looked up relative to `root`.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct ModuleRootSource {
+    root: PathBuf,
+    module_path: String,
+}
+
+impl ModuleRootSource {
+    pub fn new<P: AsRef<Path>>(root: P, module_path: String) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+            module_path,
+        }
+    }
+
+    /// Takes the module path from the `module` directive of `<root>/go.mod`, parsed the same
+    /// way as by [`ModuleResolver`](super::resolver::ModuleResolver).
+    ///
+    /// # Errors
+    ///
+    /// Will return `GoCoverageError::MissingModuleDirective` if `go.mod` has no module directive
+    pub fn from_go_mod<P: AsRef<Path>>(root: P) -> Result<Self> {
+        let (module_path, _) = read_go_mod(&root.as_ref().join("go.mod"))?;
+        Ok(Self::new(root, module_path))
+    }
+
+    pub fn root(&self) -> &PathBuf {
+        &self.root
+    }
+
+    pub fn module_path(&self) -> &String {
+        &self.module_path
+    }
+
+    /// Local path of a profile file name, `None` if it isn't part of the module.
+    pub fn path(&self, filename: &str) -> Option<PathBuf> {
+        let relative = filename
+            .strip_prefix(&self.module_path)
+            .and_then(|rest| rest.strip_prefix('/'))?;
+        Some(self.root.join(relative))
+    }
+}
+
+impl SourceProvider for ModuleRootSource {
+    fn source(&self, filename: &str) -> Result<String> {
+        let path = self
+            .path(filename)
+            .ok_or_else(|| GoCoverageError::SourceNotFound(filename.to_string()))?;
+        fs::read_to_string(path)
+            .map_err(|_| GoCoverageError::SourceNotFound(filename.to_string()).into())
+    }
+}
+
+/// Position of a function declaration with a body in a source file. Lines and columns are
+/// 1-based, columns count bytes, and the end is the position right after the closing brace,
+/// the same as Go's `go/ast` positions.
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct GoFuncExtent {
+    name: String,
+    start_line: u32,
+    start_col: u32,
+    end_line: u32,
+    end_col: u32,
+}
+
+impl GoFuncExtent {
+    pub fn new(name: String, start_line: u32, start_col: u32, end_line: u32, end_col: u32) -> Self {
+        Self {
+            name,
+            start_line,
+            start_col,
+            end_line,
+            end_col,
+        }
+    }
+
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn start_line(&self) -> u32 {
+        self.start_line
+    }
+
+    pub fn start_col(&self) -> u32 {
+        self.start_col
+    }
+
+    pub fn end_line(&self) -> u32 {
+        self.end_line
+    }
+
+    pub fn end_col(&self) -> u32 {
+        self.end_col
+    }
+}
+
+/// Finds the top-level function and method declarations of a Go source file, in source order.
+/// Declarations without a body, i.e. functions implemented in assembly, are skipped.
+///
+/// This is a scanner, not a parser: the source is expected to be valid Go.
+pub fn find_funcs(source: &str) -> Vec<GoFuncExtent> {
+    let tokens = tokenize(source);
+    let mut funcs = vec![];
+    let mut depth = 0usize;
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = &tokens[i];
+        match token.kind {
+            TokenKind::Open(_) => depth += 1,
+            TokenKind::Close => depth = depth.saturating_sub(1),
+            TokenKind::Ident(ident) if ident == "func" && depth == 0 => {
+                let at_declaration_start =
+                    i == 0 || matches!(tokens[i - 1].kind, TokenKind::Semicolon);
+                if at_declaration_start {
+                    let (func, next) = func_decl(&tokens, i);
+                    funcs.extend(func);
+                    i = next;
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    funcs
+}
+
+/// Scans the declaration starting with the `func` keyword at `start`. Returns the extent if it
+/// has a body, and the index of the token after the declaration.
+fn func_decl(tokens: &[Token], start: usize) -> (Option<GoFuncExtent>, usize) {
+    let mut i = start + 1;
+    // Receiver
+    if matches!(tokens.get(i).map(|t| t.kind), Some(TokenKind::Open('('))) {
+        i = skip_balanced(tokens, i);
+    }
+    let Some(TokenKind::Ident(name)) = tokens.get(i).map(|t| t.kind) else {
+        return (None, i);
+    };
+    i += 1;
+
+    // Type parameters, parameters and results, until the body or the end of the declaration
+    while let Some(token) = tokens.get(i) {
+        match token.kind {
+            TokenKind::Open('{') => {
+                let is_type_literal = matches!(
+                    tokens[i - 1].kind,
+                    TokenKind::Ident("struct") | TokenKind::Ident("interface")
+                );
+                let end = skip_balanced(tokens, i);
+                if is_type_literal {
+                    i = end;
+                    continue;
+                }
+                let func = tokens.get(end - 1).map(|close| GoFuncExtent {
+                    name: name.to_string(),
+                    start_line: tokens[start].line,
+                    start_col: tokens[start].col,
+                    end_line: close.line,
+                    end_col: close.col + 1,
+                });
+                return (func, end);
+            }
+            TokenKind::Open(_) => i = skip_balanced(tokens, i),
+            TokenKind::Semicolon => return (None, i),
+            _ => i += 1,
+        }
+    }
+    (None, i)
+}
+
+/// Index of the token after the bracket closing the one at `open`.
+fn skip_balanced(tokens: &[Token], open: usize) -> usize {
+    let mut depth = 0usize;
+    for (i, token) in tokens.iter().enumerate().skip(open) {
+        match token.kind {
+            TokenKind::Open(_) => depth += 1,
+            TokenKind::Close => {
+                depth -= 1;
+                if depth == 0 {
+                    return i + 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    tokens.len()
+}
+
+#[derive(Clone, Copy, Debug)]
+enum TokenKind<'a> {
+    Ident(&'a str),
+    /// Number, string or rune literal
+    Literal,
+    Open(char),
+    Close,
+    /// Explicit or automatically inserted semicolon
+    Semicolon,
+    /// `++` and `--`, which end a statement at the end of a line
+    IncDec,
+    Operator,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Token<'a> {
+    kind: TokenKind<'a>,
+    line: u32,
+    /// 1-based byte column
+    col: u32,
+}
+
+impl Token<'_> {
+    /// Whether a newline after the token inserts a semicolon, see the Go spec
+    fn ends_statement(&self) -> bool {
+        match self.kind {
+            TokenKind::Ident(ident) => {
+                !is_keyword(ident)
+                    || matches!(ident, "break" | "continue" | "fallthrough" | "return")
+            }
+            TokenKind::Literal | TokenKind::Close | TokenKind::IncDec => true,
+            TokenKind::Open(_) | TokenKind::Semicolon | TokenKind::Operator => false,
+        }
+    }
+}
+
+fn is_keyword(ident: &str) -> bool {
+    matches!(
+        ident,
+        "break"
+            | "case"
+            | "chan"
+            | "const"
+            | "continue"
+            | "default"
+            | "defer"
+            | "else"
+            | "fallthrough"
+            | "for"
+            | "func"
+            | "go"
+            | "goto"
+            | "if"
+            | "import"
+            | "interface"
+            | "map"
+            | "package"
+            | "range"
+            | "return"
+            | "select"
+            | "struct"
+            | "switch"
+            | "type"
+            | "var"
+    )
+}
+
+fn tokenize(source: &str) -> Vec<Token<'_>> {
+    let bytes = source.as_bytes();
+    let mut tokens: Vec<Token> = vec![];
+    let mut line = 1u32;
+    let mut line_start = 0usize;
+    let mut i = 0;
+
+    let newline = |tokens: &mut Vec<Token>, line: u32, col: u32| {
+        if tokens.last().is_some_and(Token::ends_statement) {
+            tokens.push(Token {
+                kind: TokenKind::Semicolon,
+                line,
+                col,
+            });
+        }
+    };
+
+    while i < bytes.len() {
+        let col = (i - line_start + 1) as u32;
+        let b = bytes[i];
+        let kind = match b {
+            b'\n' => {
+                newline(&mut tokens, line, col);
+                line += 1;
+                line_start = i + 1;
+                i += 1;
+                continue;
+            }
+            b' ' | b'\t' | b'\r' => {
+                i += 1;
+                continue;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+                continue;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                let end = source[i + 2..]
+                    .find("*/")
+                    .map_or(bytes.len(), |e| i + 2 + e + 2);
+                // A comment containing newlines acts like a newline
+                if let Some(last_newline) = source[i..end].rfind('\n') {
+                    newline(&mut tokens, line, col);
+                    line += source[i..end].matches('\n').count() as u32;
+                    line_start = i + last_newline + 1;
+                }
+                i = end;
+                continue;
+            }
+            b'"' | b'\'' => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != b && bytes[i] != b'\n' {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+                i += 1;
+                TokenKind::Literal
+            }
+            b'`' => {
+                let end = source[i + 1..]
+                    .find('`')
+                    .map_or(bytes.len(), |e| i + 1 + e + 1);
+                if let Some(last_newline) = source[i..end].rfind('\n') {
+                    line += source[i..end].matches('\n').count() as u32;
+                    line_start = i + last_newline + 1;
+                }
+                // The token starts where the literal starts
+                tokens.push(Token {
+                    kind: TokenKind::Literal,
+                    line,
+                    col,
+                });
+                i = end;
+                continue;
+            }
+            b'(' | b'[' | b'{' => {
+                i += 1;
+                TokenKind::Open(b as char)
+            }
+            b')' | b']' | b'}' => {
+                i += 1;
+                TokenKind::Close
+            }
+            b';' => {
+                i += 1;
+                TokenKind::Semicolon
+            }
+            b'+' | b'-' if bytes.get(i + 1) == Some(&b) => {
+                i += 2;
+                TokenKind::IncDec
+            }
+            b'0'..=b'9' => {
+                i = scan_number(bytes, i);
+                TokenKind::Literal
+            }
+            b'.' if bytes.get(i + 1).is_some_and(u8::is_ascii_digit) => {
+                i = scan_number(bytes, i);
+                TokenKind::Literal
+            }
+            b if b == b'_' || b.is_ascii_alphabetic() || b >= 0x80 => {
+                let start = i;
+                while let Some(c) = source[i..].chars().next() {
+                    if c == '_' || c.is_alphanumeric() {
+                        i += c.len_utf8();
+                    } else {
+                        break;
+                    }
+                }
+                if start == i {
+                    // Not a letter, e.g. a non-ASCII operator-like character
+                    i += source[i..].chars().next().map_or(1, char::len_utf8);
+                    TokenKind::Operator
+                } else {
+                    TokenKind::Ident(&source[start..i])
+                }
+            }
+            _ => {
+                i += 1;
+                TokenKind::Operator
+            }
+        };
+        tokens.push(Token { kind, line, col });
+    }
+    tokens
+}
+
+fn scan_number(bytes: &[u8], mut i: usize) -> usize {
+    while i < bytes.len() {
+        let b = bytes[i];
+        let is_exponent_sign =
+            (b == b'+' || b == b'-') && matches!(bytes[i - 1], b'e' | b'E' | b'p' | b'P');
+        if b.is_ascii_alphanumeric() || b == b'_' || b == b'.' || is_exponent_sign {
+            i += 1;
+        } else {
+            break;
+        }
+    }
+    i
+}
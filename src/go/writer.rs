@@ -1,11 +1,22 @@
-use crate::go::GoReport;
+use super::{GoProfileBlock, GoProfileMode, GoReport, Result};
 
 use std::fmt::Display;
+use std::io::Write;
 
 impl Display for GoReport {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "mode: {}", self.mode.as_str())?;
-        for super::GoProfileBlock {
+        for block in &self.blocks {
+            writeln!(f, "{block}")?
+        }
+        Ok(())
+    }
+}
+
+/// A line of a profile, without the trailing newline.
+impl Display for GoProfileBlock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let GoProfileBlock {
             filename,
             start_line,
             start_col,
@@ -13,13 +24,69 @@ impl Display for GoReport {
             end_col,
             number_of_statements,
             count,
-        } in &self.blocks
-        {
-            writeln!(
-                f,
-                "{filename}:{start_line}.{start_col},{end_line}.{end_col} {number_of_statements} {count}"
-            )?
+        } = self;
+        write!(
+            f,
+            "{filename}:{start_line}.{start_col},{end_line}.{end_col} {number_of_statements} {count}"
+        )
+    }
+}
+
+impl GoReport {
+    /// Writes the report in the text format of `go test -coverprofile`, the same as its
+    /// `Display` output without building it in memory first.
+    pub fn write_to<W: Write>(&self, w: &mut W) -> Result<()> {
+        let mut writer = GoProfileWriter::new(w, &self.mode)?;
+        for block in &self.blocks {
+            writer.write_block(block)?;
         }
+        writer.finish()?;
         Ok(())
     }
 }
+
+/// Writes a profile one block at a time, the counterpart of
+/// [`GoProfileReader`](super::reader::GoProfileReader).
+pub struct GoProfileWriter<W: Write> {
+    w: W,
+}
+
+impl<W: Write> GoProfileWriter<W> {
+    /// Writes the `mode:` header.
+    pub fn new(mut w: W, mode: &GoProfileMode) -> Result<Self> {
+        writeln!(w, "mode: {}", mode.as_str())?;
+        Ok(Self { w })
+    }
+
+    pub fn write_block(&mut self, block: &GoProfileBlock) -> Result<()> {
+        writeln!(self.w, "{block}")?;
+        Ok(())
+    }
+
+    /// Flushes the underlying writer and returns it.
+    pub fn finish(mut self) -> Result<W> {
+        self.w.flush()?;
+        Ok(self.w)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profile_round_trip_is_byte_exact() {
+        let bytes = include_bytes!("../../examples/files/coverage.out");
+        let report = GoReport::from_buf_read(&mut bytes.as_slice()).unwrap();
+        let mut written = vec![];
+        report.write_to(&mut written).unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&written),
+            String::from_utf8_lossy(bytes)
+        );
+        assert_eq!(
+            GoReport::from_buf_read(&mut written.as_slice()).unwrap(),
+            report
+        );
+    }
+}
@@ -0,0 +1,975 @@
+use super::class_file::{
+    self, ACC_MODULE, ACC_PRIVATE, ACC_SYNTHETIC, ClassFile, Code, MethodInfo,
+};
+use super::{Error, ExecutionData, JacocoError, JacocoReport, Result, class_id};
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::Path;
+use std::str::FromStr;
+
+#[cfg(feature = "serialization")]
+use serde::{Deserialize, Serialize};
+
+/// A counter of missed and covered items, e.g. instructions or branches.
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Counter {
+    missed: u32,
+    covered: u32,
+}
+
+/// The entities JaCoCo counts coverage for.
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub enum CounterEntity {
+    Instruction,
+    Branch,
+    Line,
+    Complexity,
+    Method,
+    Class,
+}
+
+/// One [`Counter`] per [`CounterEntity`].
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Counters {
+    instruction: Counter,
+    branch: Counter,
+    line: Counter,
+    complexity: Counter,
+    method: Counter,
+    class: Counter,
+}
+
+/// Instruction and branch coverage of a single source line.
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct LineCoverage {
+    instructions: Counter,
+    branches: Counter,
+}
+
+/// Coverage of a single method.
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct MethodCoverage {
+    name: String,
+    desc: String,
+    counters: Counters,
+    lines: BTreeMap<u32, LineCoverage>,
+}
+
+/// Coverage of a single class, computed from its class file and its probes.
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct ClassCoverage {
+    /// class identifier
+    id: i64,
+    /// VM name
+    name: String,
+    /// name of the source file as declared in the class file
+    source_file: Option<String>,
+    /// execution data exists for a class with the same name, but a different id
+    no_match: bool,
+    counters: Counters,
+    methods: Vec<MethodCoverage>,
+    lines: BTreeMap<u32, LineCoverage>,
+    /// source line of the instruction each probe belongs to
+    probe_lines: Vec<Option<u32>>,
+}
+
+impl Counter {
+    pub fn new(missed: u32, covered: u32) -> Self {
+        Self { missed, covered }
+    }
+
+    pub fn missed(&self) -> u32 {
+        self.missed
+    }
+
+    pub fn covered(&self) -> u32 {
+        self.covered
+    }
+
+    /// Saturates at `u32::MAX`, as do counters summed up with other counters.
+    pub fn total(&self) -> u32 {
+        self.missed.saturating_add(self.covered)
+    }
+
+    /// Ratio of covered items, `NaN` if there are no items at all.
+    pub fn covered_ratio(&self) -> f64 {
+        f64::from(self.covered) / f64::from(self.total())
+    }
+
+    pub(super) fn increment(&mut self, other: Counter) {
+        self.missed = self.missed.saturating_add(other.missed);
+        self.covered = self.covered.saturating_add(other.covered);
+    }
+}
+
+impl CounterEntity {
+    pub(super) const ALL: [Self; 6] = [
+        Self::Instruction,
+        Self::Branch,
+        Self::Line,
+        Self::Complexity,
+        Self::Method,
+        Self::Class,
+    ];
+
+    /// Name used for the entity in JaCoCo's XML and CSV reports.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CounterEntity::Instruction => "INSTRUCTION",
+            CounterEntity::Branch => "BRANCH",
+            CounterEntity::Line => "LINE",
+            CounterEntity::Complexity => "COMPLEXITY",
+            CounterEntity::Method => "METHOD",
+            CounterEntity::Class => "CLASS",
+        }
+    }
+}
+
+impl FromStr for CounterEntity {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|entity| entity.as_str() == s)
+            .ok_or_else(|| JacocoError::InvalidCounterEntity(s.to_string()).into())
+    }
+}
+
+impl Counters {
+    pub fn instruction(&self) -> Counter {
+        self.instruction
+    }
+
+    pub fn branch(&self) -> Counter {
+        self.branch
+    }
+
+    pub fn line(&self) -> Counter {
+        self.line
+    }
+
+    pub fn complexity(&self) -> Counter {
+        self.complexity
+    }
+
+    pub fn method(&self) -> Counter {
+        self.method
+    }
+
+    pub fn class(&self) -> Counter {
+        self.class
+    }
+
+    pub fn get(&self, entity: CounterEntity) -> Counter {
+        match entity {
+            CounterEntity::Instruction => self.instruction,
+            CounterEntity::Branch => self.branch,
+            CounterEntity::Line => self.line,
+            CounterEntity::Complexity => self.complexity,
+            CounterEntity::Method => self.method,
+            CounterEntity::Class => self.class,
+        }
+    }
+
+    pub fn get_mut(&mut self, entity: CounterEntity) -> &mut Counter {
+        match entity {
+            CounterEntity::Instruction => &mut self.instruction,
+            CounterEntity::Branch => &mut self.branch,
+            CounterEntity::Line => &mut self.line,
+            CounterEntity::Complexity => &mut self.complexity,
+            CounterEntity::Method => &mut self.method,
+            CounterEntity::Class => &mut self.class,
+        }
+    }
+}
+
+impl LineCoverage {
+    pub fn new(instructions: Counter, branches: Counter) -> Self {
+        Self {
+            instructions,
+            branches,
+        }
+    }
+
+    pub fn instructions(&self) -> Counter {
+        self.instructions
+    }
+
+    pub fn branches(&self) -> Counter {
+        self.branches
+    }
+
+    pub(super) fn increment(&mut self, other: &LineCoverage) {
+        self.instructions.increment(other.instructions);
+        self.branches.increment(other.branches);
+    }
+}
+
+impl MethodCoverage {
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn desc(&self) -> &String {
+        &self.desc
+    }
+
+    pub fn counters(&self) -> &Counters {
+        &self.counters
+    }
+
+    pub fn lines(&self) -> &BTreeMap<u32, LineCoverage> {
+        &self.lines
+    }
+
+    pub fn first_line(&self) -> Option<u32> {
+        self.lines.keys().next().copied()
+    }
+}
+
+impl ClassCoverage {
+    /// Analyzes a class file, the same way JaCoCo's `Analyzer` does. Probes are inserted like
+    /// JaCoCo's `ClassProbesAdapter` does it and are mapped to instructions, branches and
+    /// lines. With `probes` set to `None` the class is considered not executed.
+    ///
+    /// Methods are filtered the same way JaCoCo does it for synthetic methods, enum `values`
+    /// and `valueOf` methods and empty private constructors. Filters working on instruction
+    /// level (e.g. for `finally` blocks or try-with-resources) are not applied, so counters of
+    /// such methods may differ from JaCoCo's.
+    ///
+    /// Returns `None` for synthetic classes and modules, which JaCoCo doesn't analyze.
+    ///
+    /// # Errors
+    ///
+    /// Will return `JacocoError::InvalidClassFile` if the class file can't be parsed,
+    /// `JacocoError::UnsupportedSubroutines` if a method uses `jsr` or `ret` and
+    /// `JacocoError::IllegalStateIncompatibleProbes` if the number of probes doesn't match
+    /// the class
+    pub fn analyze(bytes: &[u8], probes: Option<&[bool]>) -> Result<Option<Self>> {
+        let class_file = ClassFile::parse(bytes)?;
+        if class_file.access_flags & (ACC_SYNTHETIC | ACC_MODULE) != 0 {
+            return Ok(None);
+        }
+        let id = class_id(bytes);
+
+        let mut probe_lines = vec![];
+        let mut methods = vec![];
+        for method in &class_file.methods {
+            let Some(code) = &method.code else { continue };
+            let method_coverage =
+                analyze_method(&class_file.name, method, code, probes, &mut probe_lines)?;
+            if !is_filtered(&class_file, method, code)?
+                && method_coverage.counters.instruction.total() > 0
+            {
+                methods.push(method_coverage);
+            }
+        }
+
+        if let Some(probes) = probes
+            && probes.len() != probe_lines.len()
+        {
+            return Err(JacocoError::IllegalStateIncompatibleProbes(class_file.name, id).into());
+        }
+
+        let mut counters = Counters::default();
+        let mut lines: BTreeMap<u32, LineCoverage> = BTreeMap::new();
+        for method in &methods {
+            for entity in [
+                CounterEntity::Instruction,
+                CounterEntity::Branch,
+                CounterEntity::Complexity,
+                CounterEntity::Method,
+            ] {
+                counters
+                    .get_mut(entity)
+                    .increment(method.counters.get(entity));
+            }
+            for (line, line_coverage) in &method.lines {
+                lines.entry(*line).or_default().increment(line_coverage);
+            }
+        }
+        counters.line = line_counter(&lines);
+        counters.class = if counters.method.covered > 0 {
+            Counter::new(0, 1)
+        } else {
+            Counter::new(1, 0)
+        };
+
+        Ok(Some(Self {
+            id,
+            name: class_file.name,
+            source_file: class_file.source_file,
+            no_match: false,
+            counters,
+            methods,
+            lines,
+            probe_lines,
+        }))
+    }
+
+    pub fn id(&self) -> i64 {
+        self.id
+    }
+
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    /// VM name of the package, empty for the default package.
+    pub fn package_name(&self) -> &str {
+        self.name
+            .rsplit_once('/')
+            .map_or("", |(package, _)| package)
+    }
+
+    pub fn source_file(&self) -> Option<&String> {
+        self.source_file.as_ref()
+    }
+
+    /// Whether execution data exists for a class with the same name, but a different id, i.e.
+    /// the execution data doesn't belong to this version of the class.
+    pub fn is_no_match(&self) -> bool {
+        self.no_match
+    }
+
+    pub fn counters(&self) -> &Counters {
+        &self.counters
+    }
+
+    pub fn methods(&self) -> &Vec<MethodCoverage> {
+        &self.methods
+    }
+
+    pub fn lines(&self) -> &BTreeMap<u32, LineCoverage> {
+        &self.lines
+    }
+
+    /// Source line of the instruction each probe belongs to, indexed by probe id.
+    pub fn probe_lines(&self) -> &Vec<Option<u32>> {
+        &self.probe_lines
+    }
+}
+
+impl JacocoReport {
+    /// Analyzes a class file with the probes of the execution data with the same class id.
+    /// See [`ClassCoverage::analyze`].
+    pub fn analyze_class(&self, bytes: &[u8]) -> Result<Option<ClassCoverage>> {
+        let id = class_id(bytes);
+        let execution_data = self.execution_datas.iter().find(|data| data.id == id);
+        let Some(mut class_coverage) =
+            ClassCoverage::analyze(bytes, execution_data.map(|data| &data.probes[..]))?
+        else {
+            return Ok(None);
+        };
+        class_coverage.no_match = execution_data.is_none()
+            && self
+                .execution_datas
+                .iter()
+                .any(|data| data.name == class_coverage.name);
+        Ok(Some(class_coverage))
+    }
+
+    /// Analyzes all class files found at `path`, which can be a single `.class` file, a
+    /// JAR/WAR/EAR/ZIP archive or a directory which is scanned recursively for both. Classes
+    /// without any code are skipped, as well as classes using subroutines, see
+    /// [`JacocoReport::analyze_path_with_skipped`].
+    pub fn analyze_path<P: AsRef<Path>>(&self, path: P) -> Result<Vec<ClassCoverage>> {
+        Ok(self.analyze_path_with_skipped(path)?.0)
+    }
+
+    /// Same as [`JacocoReport::analyze_path`], but also returns why classes were skipped.
+    /// Like JaCoCo, classes of old class file versions which use subroutines (`jsr`/`ret`) are
+    /// reported with `JacocoError::UnsupportedSubroutines` and the other classes are analyzed.
+    ///
+    /// # Errors
+    ///
+    /// Will return an I/O or archive error if `path` can't be read, and the error of the first
+    /// class file which can't be parsed
+    pub fn analyze_path_with_skipped<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> Result<(Vec<ClassCoverage>, Vec<Error>)> {
+        let by_id: HashMap<i64, &ExecutionData> = self
+            .execution_datas
+            .iter()
+            .map(|data| (data.id, data))
+            .collect();
+        let names: HashSet<&str> = self
+            .execution_datas
+            .iter()
+            .map(|data| data.name.as_str())
+            .collect();
+
+        let mut classes = vec![];
+        let mut skipped = vec![];
+        class_file::for_each_class_file(path, &mut |bytes| {
+            let execution_data = by_id.get(&class_id(bytes));
+            let probes = execution_data.map(|data| &data.probes[..]);
+            let class_coverage = match ClassCoverage::analyze(bytes, probes) {
+                Ok(class_coverage) => class_coverage,
+                Err(e @ Error::Jacoco(JacocoError::UnsupportedSubroutines(_))) => {
+                    skipped.push(e);
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            };
+            if let Some(mut class_coverage) = class_coverage
+                && class_coverage.counters.instruction.total() > 0
+            {
+                class_coverage.no_match =
+                    execution_data.is_none() && names.contains(class_coverage.name.as_str());
+                classes.push(class_coverage);
+            }
+            Ok(())
+        })?;
+        Ok((classes, skipped))
+    }
+}
+
+/// Mirrors JaCoCo's method level filters.
+fn is_filtered(class_file: &ClassFile, method: &MethodInfo, code: &Code) -> Result<bool> {
+    // SyntheticFilter, lambda bodies are synthetic but contain user code
+    if method.access_flags & ACC_SYNTHETIC != 0 && !method.name.starts_with("lambda$") {
+        return Ok(true);
+    }
+
+    let super_name = class_file.super_name.as_deref().unwrap_or_default();
+    let is_enum = super_name == "java/lang/Enum";
+
+    // EnumFilter
+    if is_enum
+        && ((method.name == "values" && method.desc == format!("()[L{};", class_file.name))
+            || (method.name == "valueOf"
+                && method.desc == format!("(Ljava/lang/String;)L{};", class_file.name)))
+    {
+        return Ok(true);
+    }
+
+    if method.access_flags & ACC_PRIVATE == 0 || method.name != "<init>" {
+        return Ok(false);
+    }
+
+    // PrivateEmptyNoArgConstructorFilter: aload_0, invokespecial super.<init>()V, return
+    if method.desc == "()V"
+        && let [0x2A, 0xB7, hi, lo, 0xB1] = code.code[..]
+    {
+        let (owner, name, desc) = class_file.member_ref(u16::from_be_bytes([hi, lo]))?;
+        return Ok(owner == super_name && name == "<init>" && desc == "()V");
+    }
+
+    // EnumEmptyConstructorFilter: aload_0, aload_1, iload_2, invokespecial Enum.<init>, return
+    if is_enum
+        && method.desc == "(Ljava/lang/String;I)V"
+        && let [0x2A, 0x2B, 0x1C, 0xB7, hi, lo, 0xB1] = code.code[..]
+    {
+        let (owner, name, desc) = class_file.member_ref(u16::from_be_bytes([hi, lo]))?;
+        return Ok(owner == "java/lang/Enum"
+            && name == "<init>"
+            && desc == "(Ljava/lang/String;I)V");
+    }
+
+    Ok(false)
+}
+
+pub(super) fn line_counter(lines: &BTreeMap<u32, LineCoverage>) -> Counter {
+    let mut counter = Counter::default();
+    for line in lines.values().filter(|line| line.instructions.total() > 0) {
+        if line.instructions.covered > 0 {
+            counter.covered += 1;
+        } else {
+            counter.missed += 1;
+        }
+    }
+    counter
+}
+
+enum InsnKind {
+    Plain,
+    MethodInvocation,
+    /// `xRETURN` and `ATHROW`
+    Exit,
+    Jump {
+        is_goto: bool,
+        target: usize,
+    },
+    Switch {
+        default: usize,
+        targets: Vec<usize>,
+    },
+}
+
+struct Insn {
+    offset: usize,
+    kind: InsnKind,
+}
+
+/// Decodes the instructions of a method, only keeping what's relevant for control flow.
+fn decode_instructions(class_name: &str, code: &[u8]) -> Result<Vec<Insn>> {
+    let invalid =
+        |reason: &str| -> crate::Error { JacocoError::InvalidClassFile(reason.to_string()).into() };
+    let u8_at = |at: usize| {
+        code.get(at)
+            .copied()
+            .ok_or_else(|| invalid("truncated code"))
+    };
+    let i16_at = |at: usize| -> Result<i64> {
+        Ok(i64::from(i16::from_be_bytes([u8_at(at)?, u8_at(at + 1)?])))
+    };
+    let i32_at = |at: usize| -> Result<i64> {
+        Ok(i64::from(i32::from_be_bytes([
+            u8_at(at)?,
+            u8_at(at + 1)?,
+            u8_at(at + 2)?,
+            u8_at(at + 3)?,
+        ])))
+    };
+    let target = |offset: usize, relative: i64| -> Result<usize> {
+        usize::try_from(offset as i64 + relative)
+            .ok()
+            .filter(|target| *target < code.len())
+            .ok_or_else(|| invalid("jump target out of bounds"))
+    };
+
+    let mut instructions = vec![];
+    let mut offset = 0;
+    while offset < code.len() {
+        let opcode = code[offset];
+        let (kind, length) = match opcode {
+            0x00..=0x0F | 0x1A..=0x35 | 0x3B..=0x83 | 0x85..=0x98 | 0xBE | 0xC2 | 0xC3 => {
+                (InsnKind::Plain, 1)
+            }
+            0x10 | 0x12 | 0x15..=0x19 | 0x36..=0x3A | 0xBC => (InsnKind::Plain, 2),
+            0x11 | 0x13 | 0x14 | 0x84 | 0xB2..=0xB5 | 0xBB | 0xBD | 0xC0 | 0xC1 => {
+                (InsnKind::Plain, 3)
+            }
+            0xC5 => (InsnKind::Plain, 4),
+            0xC4 => (
+                InsnKind::Plain,
+                if u8_at(offset + 1)? == 0x84 { 6 } else { 4 },
+            ),
+            0xB6..=0xB8 => (InsnKind::MethodInvocation, 3),
+            0xB9 | 0xBA => (InsnKind::MethodInvocation, 5),
+            0xAC..=0xB1 | 0xBF => (InsnKind::Exit, 1),
+            0x99..=0xA7 | 0xC6 | 0xC7 => {
+                let target = target(offset, i16_at(offset + 1)?)?;
+                (
+                    InsnKind::Jump {
+                        is_goto: opcode == 0xA7,
+                        target,
+                    },
+                    3,
+                )
+            }
+            0xC8 => {
+                let target = target(offset, i32_at(offset + 1)?)?;
+                (
+                    InsnKind::Jump {
+                        is_goto: true,
+                        target,
+                    },
+                    5,
+                )
+            }
+            0xAA | 0xAB => {
+                let base = offset + 1 + (3 - offset % 4);
+                let default = target(offset, i32_at(base)?)?;
+                let mut targets = vec![];
+                let length = if opcode == 0xAA {
+                    let low = i32_at(base + 4)?;
+                    let high = i32_at(base + 8)?;
+                    let count = usize::try_from(high - low + 1)
+                        .map_err(|_| invalid("invalid tableswitch bounds"))?;
+                    for i in 0..count {
+                        targets.push(target(offset, i32_at(base + 12 + 4 * i)?)?);
+                    }
+                    base + 12 + 4 * count - offset
+                } else {
+                    let count = usize::try_from(i32_at(base + 4)?)
+                        .map_err(|_| invalid("invalid lookupswitch size"))?;
+                    for i in 0..count {
+                        targets.push(target(offset, i32_at(base + 12 + 8 * i)?)?);
+                    }
+                    base + 8 + 8 * count - offset
+                };
+                (InsnKind::Switch { default, targets }, length)
+            }
+            0xA8 | 0xA9 | 0xC9 => {
+                return Err(JacocoError::UnsupportedSubroutines(class_name.to_string()).into());
+            }
+            _ => return Err(invalid(&format!("unknown opcode {opcode:#04x}"))),
+        };
+        instructions.push(Insn { offset, kind });
+        offset += length;
+    }
+    Ok(instructions)
+}
+
+/// Mirrors JaCoCo's `LabelInfo`. As ASM creates a single label per bytecode offset, labels
+/// are identified by their offset.
+#[derive(Clone, Copy, Default)]
+struct LabelInfo {
+    exists: bool,
+    target: bool,
+    successor: bool,
+    multi_target: bool,
+    method_invocation_line: bool,
+    done: bool,
+    probe_id: Option<usize>,
+}
+
+impl LabelInfo {
+    fn set_target(&mut self) {
+        if self.target || self.successor {
+            self.multi_target = true;
+        } else {
+            self.target = true;
+        }
+    }
+
+    fn set_successor(&mut self) {
+        self.successor = true;
+        if self.target {
+            self.multi_target = true;
+        }
+    }
+
+    fn needs_probe(&self) -> bool {
+        self.successor && (self.multi_target || self.method_invocation_line)
+    }
+}
+
+/// Mirrors JaCoCo's `Instruction`.
+#[derive(Default)]
+struct Instruction {
+    line: Option<u32>,
+    branches: u32,
+    covered_branches: BTreeSet<u32>,
+    predecessor: Option<(usize, u32)>,
+}
+
+/// Mirrors JaCoCo's `InstructionsBuilder`.
+struct InstructionsBuilder<'a> {
+    probes: Option<&'a [bool]>,
+    current_line: Option<u32>,
+    current_insn: Option<usize>,
+    instructions: Vec<Instruction>,
+    insn_by_offset: HashMap<usize, usize>,
+    jumps: Vec<(usize, usize, u32)>,
+}
+
+impl InstructionsBuilder<'_> {
+    fn add_label(&mut self, label: &LabelInfo) {
+        if !label.successor {
+            self.current_insn = None;
+        }
+    }
+
+    fn add_instruction(&mut self, offset: usize) {
+        let insn = self.instructions.len();
+        self.instructions.push(Instruction {
+            line: self.current_line,
+            ..Default::default()
+        });
+        self.insn_by_offset.insert(offset, insn);
+        if let Some(current_insn) = self.current_insn {
+            self.add_branch_to(current_insn, insn, 0);
+        }
+        self.current_insn = Some(insn);
+    }
+
+    fn add_jump(&mut self, target: usize, branch: u32) {
+        if let Some(current_insn) = self.current_insn {
+            self.jumps.push((current_insn, target, branch));
+        }
+    }
+
+    /// Returns the line of the instruction the probe belongs to.
+    fn add_probe(&mut self, probe_id: usize, branch: u32) -> Option<u32> {
+        let executed = self
+            .probes
+            .is_some_and(|probes| probes.get(probe_id).copied().unwrap_or(false));
+        let current_insn = self.current_insn?;
+        self.instructions[current_insn].branches += 1;
+        if executed {
+            self.propagate_executed_branch(current_insn, branch);
+        }
+        self.instructions[current_insn].line
+    }
+
+    fn no_successor(&mut self) {
+        self.current_insn = None;
+    }
+
+    fn add_branch_to(&mut self, source: usize, target: usize, branch: u32) {
+        self.instructions[source].branches += 1;
+        self.instructions[target].predecessor = Some((source, branch));
+        if !self.instructions[target].covered_branches.is_empty() {
+            self.propagate_executed_branch(source, branch);
+        }
+    }
+
+    fn propagate_executed_branch(&mut self, insn: usize, branch: u32) {
+        // No recursion here, as there can be very long chains of instructions
+        let mut next = Some((insn, branch));
+        while let Some((insn, branch)) = next {
+            let instruction = &mut self.instructions[insn];
+            if !instruction.covered_branches.is_empty() {
+                instruction.covered_branches.insert(branch);
+                break;
+            }
+            instruction.covered_branches.insert(branch);
+            next = instruction.predecessor;
+        }
+    }
+
+    fn wire_jumps(&mut self) {
+        for (source, target, branch) in std::mem::take(&mut self.jumps) {
+            if let Some(&target) = self.insn_by_offset.get(&target) {
+                self.add_branch_to(source, target, branch);
+            }
+        }
+    }
+}
+
+fn analyze_method(
+    class_name: &str,
+    method: &MethodInfo,
+    code: &Code,
+    probes: Option<&[bool]>,
+    probe_lines: &mut Vec<Option<u32>>,
+) -> Result<MethodCoverage> {
+    let instructions = decode_instructions(class_name, &code.code)?;
+
+    let mut labels = vec![LabelInfo::default(); code.code.len() + 1];
+    let mut line_numbers: HashMap<usize, Vec<u32>> = HashMap::new();
+    for entry in &code.exception_table {
+        for offset in [entry.start_pc, entry.end_pc, entry.handler_pc] {
+            if let Some(label) = labels.get_mut(usize::from(offset)) {
+                label.exists = true;
+            }
+        }
+    }
+    for &(start_pc, line) in &code.line_numbers {
+        if let Some(label) = labels.get_mut(usize::from(start_pc)) {
+            label.exists = true;
+            line_numbers
+                .entry(start_pc.into())
+                .or_default()
+                .push(line.into());
+        }
+    }
+    for insn in &instructions {
+        match &insn.kind {
+            InsnKind::Jump { target, .. } => labels[*target].exists = true,
+            InsnKind::Switch { default, targets } => {
+                for target in std::iter::once(default).chain(targets) {
+                    labels[*target].exists = true;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // LabelFlowAnalyzer
+    for entry in code.exception_table.iter().rev() {
+        for offset in [entry.start_pc, entry.handler_pc] {
+            if let Some(label) = labels.get_mut(usize::from(offset)) {
+                label.set_target();
+            }
+        }
+    }
+    let mut successor = false;
+    let mut first = true;
+    let mut line_start = None;
+    for insn in &instructions {
+        let label = &mut labels[insn.offset];
+        if label.exists {
+            if first {
+                label.set_target();
+            }
+            if successor {
+                label.set_successor();
+            }
+        }
+        if line_numbers.contains_key(&insn.offset) {
+            line_start = Some(insn.offset);
+        }
+
+        match &insn.kind {
+            InsnKind::Plain => successor = true,
+            InsnKind::MethodInvocation => {
+                successor = true;
+                if let Some(line_start) = line_start {
+                    labels[line_start].method_invocation_line = true;
+                }
+            }
+            InsnKind::Exit => successor = false,
+            InsnKind::Jump { is_goto, target } => {
+                labels[*target].set_target();
+                successor = !is_goto;
+            }
+            InsnKind::Switch { default, targets } => {
+                for target in std::iter::once(default).chain(targets) {
+                    labels[*target].done = false;
+                }
+                for target in std::iter::once(default).chain(targets) {
+                    if !labels[*target].done {
+                        labels[*target].set_target();
+                        labels[*target].done = true;
+                    }
+                }
+                successor = false;
+            }
+        }
+        first = false;
+    }
+    if successor && labels[code.code.len()].exists {
+        labels[code.code.len()].set_successor();
+    }
+
+    // MethodProbesAdapter driving MethodAnalyzer
+    let mut builder = InstructionsBuilder {
+        probes,
+        current_line: None,
+        current_insn: None,
+        instructions: Vec::with_capacity(instructions.len()),
+        insn_by_offset: HashMap::with_capacity(instructions.len()),
+        jumps: vec![],
+    };
+    let next_probe =
+        |builder: &mut InstructionsBuilder, probe_lines: &mut Vec<Option<u32>>, branch: u32| {
+            let probe_id = probe_lines.len();
+            let line = builder.add_probe(probe_id, branch);
+            probe_lines.push(line);
+        };
+
+    for insn in &instructions {
+        let label = labels[insn.offset];
+        if label.exists {
+            if label.needs_probe() {
+                next_probe(&mut builder, probe_lines, 0);
+                builder.no_successor();
+            }
+            builder.add_label(&label);
+        }
+        if let Some(lines) = line_numbers.get(&insn.offset) {
+            builder.current_line = lines.last().copied();
+        }
+
+        match &insn.kind {
+            InsnKind::Plain | InsnKind::MethodInvocation => builder.add_instruction(insn.offset),
+            InsnKind::Exit => {
+                builder.add_instruction(insn.offset);
+                next_probe(&mut builder, probe_lines, 0);
+            }
+            InsnKind::Jump { target, .. } => {
+                builder.add_instruction(insn.offset);
+                if labels[*target].multi_target {
+                    next_probe(&mut builder, probe_lines, 1);
+                } else {
+                    builder.add_jump(*target, 1);
+                }
+            }
+            InsnKind::Switch { default, targets } => {
+                // MethodProbesAdapter.markLabels assigns the probe ids up front
+                let all_targets = || std::iter::once(default).chain(targets);
+                for target in all_targets() {
+                    labels[*target].done = false;
+                    labels[*target].probe_id = None;
+                }
+                let mut has_probes = false;
+                for target in all_targets() {
+                    if labels[*target].multi_target && !labels[*target].done {
+                        labels[*target].probe_id = Some(probe_lines.len());
+                        probe_lines.push(None);
+                        has_probes = true;
+                    }
+                    labels[*target].done = true;
+                }
+
+                builder.add_instruction(insn.offset);
+                for target in all_targets() {
+                    labels[*target].done = false;
+                }
+                if has_probes {
+                    for (branch, target) in (0..).zip(all_targets()) {
+                        if labels[*target].done {
+                            continue;
+                        }
+                        match labels[*target].probe_id {
+                            Some(probe_id) => {
+                                probe_lines[probe_id] = builder.add_probe(probe_id, branch);
+                            }
+                            None => builder.add_jump(*target, branch),
+                        }
+                        labels[*target].done = true;
+                    }
+                } else {
+                    let mut branch = 0;
+                    builder.add_jump(*default, branch);
+                    labels[*default].done = true;
+                    for target in targets {
+                        if !labels[*target].done {
+                            branch += 1;
+                            builder.add_jump(*target, branch);
+                            labels[*target].done = true;
+                        }
+                    }
+                }
+            }
+        }
+    }
+    builder.wire_jumps();
+
+    // MethodCoverageCalculator
+    let mut counters = Counters::default();
+    let mut lines: BTreeMap<u32, LineCoverage> = BTreeMap::new();
+    for instruction in &builder.instructions {
+        let covered_branches = u32::try_from(instruction.covered_branches.len())?;
+        let instructions = if covered_branches == 0 {
+            Counter::new(1, 0)
+        } else {
+            Counter::new(0, 1)
+        };
+        let branches = if instruction.branches < 2 {
+            Counter::default()
+        } else {
+            Counter::new(instruction.branches - covered_branches, covered_branches)
+        };
+
+        counters.instruction.increment(instructions);
+        counters.branch.increment(branches);
+        if branches.total() > 1 {
+            let c = branches.covered.saturating_sub(1);
+            let m = (branches.total() - c).saturating_sub(1);
+            counters.complexity.increment(Counter::new(m, c));
+        }
+        if let Some(line) = instruction.line {
+            let line = lines.entry(line).or_default();
+            line.instructions.increment(instructions);
+            line.branches.increment(branches);
+        }
+    }
+    let method_counter = if counters.instruction.covered == 0 {
+        Counter::new(1, 0)
+    } else {
+        Counter::new(0, 1)
+    };
+    counters.method.increment(method_counter);
+    counters.complexity.increment(method_counter);
+    counters.line = line_counter(&lines);
+
+    Ok(MethodCoverage {
+        name: method.name.clone(),
+        desc: method.desc.clone(),
+        counters,
+        lines,
+    })
+}
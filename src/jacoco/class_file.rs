@@ -0,0 +1,336 @@
+use super::{Error, JacocoError, Result};
+
+use byteorder::{BigEndian, ReadBytesExt};
+use std::fs::{self, File};
+use std::io::{Cursor, Read, Seek};
+use std::path::Path;
+
+pub(super) const ACC_PRIVATE: u16 = 0x0002;
+pub(super) const ACC_SYNTHETIC: u16 = 0x1000;
+pub(super) const ACC_MODULE: u16 = 0x8000;
+
+/// The parts of a class file needed to compute class ids and to analyze coverage.
+pub(super) struct ClassFile {
+    pub(super) access_flags: u16,
+    pub(super) name: String,
+    pub(super) super_name: Option<String>,
+    pub(super) source_file: Option<String>,
+    pub(super) methods: Vec<MethodInfo>,
+    constant_pool: Vec<Constant>,
+}
+
+pub(super) struct MethodInfo {
+    pub(super) access_flags: u16,
+    pub(super) name: String,
+    pub(super) desc: String,
+    pub(super) code: Option<Code>,
+}
+
+pub(super) struct Code {
+    pub(super) code: Vec<u8>,
+    pub(super) exception_table: Vec<ExceptionTableEntry>,
+    /// `(start_pc, line_number)` pairs in the order of the `LineNumberTable` attributes
+    pub(super) line_numbers: Vec<(u16, u16)>,
+}
+
+pub(super) struct ExceptionTableEntry {
+    pub(super) start_pc: u16,
+    pub(super) end_pc: u16,
+    pub(super) handler_pc: u16,
+}
+
+#[derive(Clone)]
+enum Constant {
+    Utf8(String),
+    Class(u16),
+    NameAndType(u16, u16),
+    MemberRef(u16, u16),
+    Other,
+}
+
+impl ClassFile {
+    pub(super) fn parse(bytes: &[u8]) -> Result<Self> {
+        let mut r = Cursor::new(bytes);
+        if r.read_u32::<BigEndian>()? != 0xCAFE_BABE {
+            return Err(invalid("wrong magic number"));
+        }
+        let _minor_version = r.read_u16::<BigEndian>()?;
+        let _major_version = r.read_u16::<BigEndian>()?;
+
+        let constant_pool = Self::read_constant_pool(&mut r)?;
+        let mut class_file = Self {
+            access_flags: 0,
+            name: String::new(),
+            super_name: None,
+            source_file: None,
+            methods: vec![],
+            constant_pool,
+        };
+
+        class_file.access_flags = r.read_u16::<BigEndian>()?;
+        class_file.name = class_file
+            .class_name(r.read_u16::<BigEndian>()?)?
+            .to_string();
+        let super_class = r.read_u16::<BigEndian>()?;
+        if super_class != 0 {
+            class_file.super_name = Some(class_file.class_name(super_class)?.to_string());
+        }
+
+        let interfaces_count = r.read_u16::<BigEndian>()?;
+        skip(&mut r, 2 * u64::from(interfaces_count))?;
+
+        let fields_count = r.read_u16::<BigEndian>()?;
+        for _ in 0..fields_count {
+            skip(&mut r, 6)?;
+            class_file.skip_attributes(&mut r)?;
+        }
+
+        let methods_count = r.read_u16::<BigEndian>()?;
+        for _ in 0..methods_count {
+            let method = class_file.read_method(&mut r)?;
+            class_file.methods.push(method);
+        }
+
+        let attributes_count = r.read_u16::<BigEndian>()?;
+        for _ in 0..attributes_count {
+            let name = class_file.utf8(r.read_u16::<BigEndian>()?)?.to_string();
+            let length = r.read_u32::<BigEndian>()?;
+            if name == "SourceFile" {
+                let source_file = class_file.utf8(r.read_u16::<BigEndian>()?)?;
+                class_file.source_file = Some(source_file.to_string());
+            } else {
+                skip(&mut r, length.into())?;
+            }
+        }
+
+        Ok(class_file)
+    }
+
+    /// Resolves a `Methodref`/`Fieldref`/`InterfaceMethodref` constant into its owner, name and
+    /// descriptor.
+    pub(super) fn member_ref(&self, index: u16) -> Result<(&str, &str, &str)> {
+        let Some(Constant::MemberRef(class_index, name_and_type_index)) =
+            self.constant_pool.get(usize::from(index))
+        else {
+            return Err(invalid("invalid member reference"));
+        };
+        let Some(Constant::NameAndType(name_index, desc_index)) =
+            self.constant_pool.get(usize::from(*name_and_type_index))
+        else {
+            return Err(invalid("invalid name and type reference"));
+        };
+        Ok((
+            self.class_name(*class_index)?,
+            self.utf8(*name_index)?,
+            self.utf8(*desc_index)?,
+        ))
+    }
+
+    fn read_constant_pool(r: &mut Cursor<&[u8]>) -> Result<Vec<Constant>> {
+        let constant_pool_count = r.read_u16::<BigEndian>()?;
+        let mut constant_pool = vec![Constant::Other; constant_pool_count.into()];
+        let mut index = 1;
+        while index < usize::from(constant_pool_count) {
+            let tag = r.read_u8()?;
+            constant_pool[index] = match tag {
+                1 => {
+                    let length = r.read_u16::<BigEndian>()?;
+                    let mut value = vec![0; length.into()];
+                    r.read_exact(&mut value)?;
+                    Constant::Utf8(String::from_utf8_lossy(&value).into_owned())
+                }
+                7 => Constant::Class(r.read_u16::<BigEndian>()?),
+                9..=11 => {
+                    Constant::MemberRef(r.read_u16::<BigEndian>()?, r.read_u16::<BigEndian>()?)
+                }
+                12 => Constant::NameAndType(r.read_u16::<BigEndian>()?, r.read_u16::<BigEndian>()?),
+                8 | 16 | 19 | 20 => {
+                    skip(r, 2)?;
+                    Constant::Other
+                }
+                15 => {
+                    skip(r, 3)?;
+                    Constant::Other
+                }
+                3 | 4 | 17 | 18 => {
+                    skip(r, 4)?;
+                    Constant::Other
+                }
+                5 | 6 => {
+                    skip(r, 8)?;
+                    Constant::Other
+                }
+                _ => return Err(invalid(&format!("unknown constant pool tag {tag}"))),
+            };
+            // Long and double entries take up two slots
+            index += if tag == 5 || tag == 6 { 2 } else { 1 };
+        }
+        Ok(constant_pool)
+    }
+
+    fn read_method(&self, r: &mut Cursor<&[u8]>) -> Result<MethodInfo> {
+        let access_flags = r.read_u16::<BigEndian>()?;
+        let name = self.utf8(r.read_u16::<BigEndian>()?)?.to_string();
+        let desc = self.utf8(r.read_u16::<BigEndian>()?)?.to_string();
+
+        let mut code = None;
+        let attributes_count = r.read_u16::<BigEndian>()?;
+        for _ in 0..attributes_count {
+            let attribute_name = self.utf8(r.read_u16::<BigEndian>()?)?;
+            let length = r.read_u32::<BigEndian>()?;
+            if attribute_name == "Code" {
+                code = Some(self.read_code(r)?);
+            } else {
+                skip(r, length.into())?;
+            }
+        }
+
+        Ok(MethodInfo {
+            access_flags,
+            name,
+            desc,
+            code,
+        })
+    }
+
+    fn read_code(&self, r: &mut Cursor<&[u8]>) -> Result<Code> {
+        let _max_stack = r.read_u16::<BigEndian>()?;
+        let _max_locals = r.read_u16::<BigEndian>()?;
+        let code_length = r.read_u32::<BigEndian>()?;
+        let mut code = vec![0; usize::try_from(code_length)?];
+        r.read_exact(&mut code)?;
+
+        let exception_table_length = r.read_u16::<BigEndian>()?;
+        let mut exception_table = Vec::with_capacity(exception_table_length.into());
+        for _ in 0..exception_table_length {
+            exception_table.push(ExceptionTableEntry {
+                start_pc: r.read_u16::<BigEndian>()?,
+                end_pc: r.read_u16::<BigEndian>()?,
+                handler_pc: r.read_u16::<BigEndian>()?,
+            });
+            let _catch_type = r.read_u16::<BigEndian>()?;
+        }
+
+        let mut line_numbers = vec![];
+        let attributes_count = r.read_u16::<BigEndian>()?;
+        for _ in 0..attributes_count {
+            let attribute_name = self.utf8(r.read_u16::<BigEndian>()?)?;
+            let length = r.read_u32::<BigEndian>()?;
+            if attribute_name == "LineNumberTable" {
+                let line_number_table_length = r.read_u16::<BigEndian>()?;
+                for _ in 0..line_number_table_length {
+                    let start_pc = r.read_u16::<BigEndian>()?;
+                    let line_number = r.read_u16::<BigEndian>()?;
+                    line_numbers.push((start_pc, line_number));
+                }
+            } else {
+                skip(r, length.into())?;
+            }
+        }
+
+        Ok(Code {
+            code,
+            exception_table,
+            line_numbers,
+        })
+    }
+
+    fn skip_attributes(&self, r: &mut Cursor<&[u8]>) -> Result<()> {
+        let attributes_count = r.read_u16::<BigEndian>()?;
+        for _ in 0..attributes_count {
+            skip(r, 2)?;
+            let length = r.read_u32::<BigEndian>()?;
+            skip(r, length.into())?;
+        }
+        Ok(())
+    }
+
+    fn utf8(&self, index: u16) -> Result<&str> {
+        match self.constant_pool.get(usize::from(index)) {
+            Some(Constant::Utf8(value)) => Ok(value),
+            _ => Err(invalid(&format!("constant {index} is not a Utf8 entry"))),
+        }
+    }
+
+    fn class_name(&self, index: u16) -> Result<&str> {
+        match self.constant_pool.get(usize::from(index)) {
+            Some(Constant::Class(name_index)) => self.utf8(*name_index),
+            _ => Err(invalid(&format!("constant {index} is not a Class entry"))),
+        }
+    }
+}
+
+/// Calls `f` with the bytes of every class file found at `path`, which can be a single
+/// `.class` file, a JAR/WAR/EAR/ZIP archive or a directory which is scanned recursively for
+/// both.
+pub(super) fn for_each_class_file<P, F>(path: P, f: &mut F) -> Result<()>
+where
+    P: AsRef<Path>,
+    F: FnMut(&[u8]) -> Result<()>,
+{
+    let path = path.as_ref();
+    if path.is_dir() {
+        let mut entries = fs::read_dir(path)?.collect::<std::io::Result<Vec<_>>>()?;
+        entries.sort_by_key(|entry| entry.path());
+        for entry in entries {
+            for_each_class_file(entry.path(), f)?;
+        }
+    } else if has_extension(path, "class") {
+        f(&fs::read(path)?)?;
+    } else if is_archive(path) {
+        for_each_archived_class_file(File::open(path)?, f)?;
+    }
+    Ok(())
+}
+
+/// Calls `f` with the bytes of every class file of a JAR/WAR/EAR/ZIP archive, including the
+/// ones in nested archives.
+pub(super) fn for_each_archived_class_file<R, F>(r: R, f: &mut F) -> Result<()>
+where
+    R: Read + Seek,
+    F: FnMut(&[u8]) -> Result<()>,
+{
+    let mut archive = zip::ZipArchive::new(r)?;
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        let entry_path = Path::new(&*file.name()?).to_path_buf();
+        if !file.is_file() {
+            continue;
+        }
+
+        if has_extension(&entry_path, "class") {
+            let mut bytes = Vec::with_capacity(usize::try_from(file.size())?);
+            file.read_to_end(&mut bytes)?;
+            f(&bytes)?;
+        } else if is_archive(&entry_path) {
+            let mut bytes = Vec::with_capacity(usize::try_from(file.size())?);
+            file.read_to_end(&mut bytes)?;
+            for_each_archived_class_file(Cursor::new(bytes), f)?;
+        }
+    }
+    Ok(())
+}
+
+fn has_extension(path: &Path, extension: &str) -> bool {
+    path.extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case(extension))
+}
+
+fn is_archive(path: &Path) -> bool {
+    ["jar", "war", "ear", "zip"]
+        .iter()
+        .any(|extension| has_extension(path, extension))
+}
+
+fn skip(r: &mut Cursor<&[u8]>, n: u64) -> Result<()> {
+    let position = r.position() + n;
+    if position > r.get_ref().len() as u64 {
+        return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+    }
+    r.set_position(position);
+    Ok(())
+}
+
+fn invalid(reason: &str) -> Error {
+    JacocoError::InvalidClassFile(reason.to_string()).into()
+}
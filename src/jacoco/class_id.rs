@@ -0,0 +1,141 @@
+use super::class_file::{self, ClassFile};
+use super::{ExecutionData, JacocoReport, Result};
+
+use std::collections::HashMap;
+use std::io::{Read, Seek};
+use std::path::Path;
+
+/// Lookup table of JaCoCo's CRC64 implementation, built from the polynomial
+/// `0xD800000000000000` (the reversed ISO 3309 polynomial).
+const LOOKUP_TABLE: [u64; 256] = {
+    const POLY64REV: u64 = 0xD800_0000_0000_0000;
+    let mut table = [0u64; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut v = i as u64;
+        let mut j = 0;
+        while j < 8 {
+            v = if (v & 1) == 1 {
+                (v >> 1) ^ POLY64REV
+            } else {
+                v >> 1
+            };
+            j += 1;
+        }
+        table[i] = v;
+        i += 1;
+    }
+    table
+};
+
+/// Class file major version of Java 8.
+const V1_8: u8 = 52;
+
+/// Class file major version of Java 9.
+const V9: u8 = 53;
+
+fn crc64_update(sum: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(sum, |sum, &b| {
+        (sum >> 8) ^ LOOKUP_TABLE[((sum ^ u64::from(b)) & 0xFF) as usize]
+    })
+}
+
+/// Computes the id JaCoCo uses to identify a class, i.e. the CRC64 checksum of the raw class
+/// file, matching `org.jacoco.core.internal.data.CRC64.classId`.
+pub fn class_id(bytes: &[u8]) -> i64 {
+    // JaCoCo computes the id of Java 9 class files as if their major version was the one of
+    // Java 8, a leftover of how early versions of Java 9 were supported
+    if bytes.len() > 7 && bytes[6] == 0x00 && bytes[7] == V9 {
+        let sum = crc64_update(0, &bytes[..7]);
+        let sum = crc64_update(sum, &[V1_8]);
+        return crc64_update(sum, &bytes[8..]) as i64;
+    }
+    crc64_update(0, bytes) as i64
+}
+
+/// Class ids of a set of class files, grouped by VM class name.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ClassIds {
+    ids_by_name: HashMap<String, Vec<i64>>,
+}
+
+/// Result of comparing the execution data of a report against [`ClassIds`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ClassIdCheck<'a> {
+    /// entries with a class of the same name and id
+    matching: Vec<&'a ExecutionData>,
+    /// entries with a class of the same name, but a different id
+    stale: Vec<&'a ExecutionData>,
+    /// entries without a class of the same name
+    missing: Vec<&'a ExecutionData>,
+}
+
+impl ClassIds {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Collects the ids of the classes at `path`, which can be a single `.class` file, a
+    /// JAR/WAR/EAR/ZIP archive or a directory which is scanned recursively for both.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let mut class_ids = Self::new();
+        class_ids.add_path(path)?;
+        Ok(class_ids)
+    }
+
+    pub fn ids(&self, name: &str) -> Option<&Vec<i64>> {
+        self.ids_by_name.get(name)
+    }
+
+    /// Adds a single class file and returns its VM name.
+    pub fn add_class(&mut self, bytes: &[u8]) -> Result<String> {
+        let name = ClassFile::parse(bytes)?.name;
+        let ids = self.ids_by_name.entry(name.clone()).or_default();
+        let id = class_id(bytes);
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+        Ok(name)
+    }
+
+    /// Adds all class files found at `path`, see [`ClassIds::from_path`].
+    pub fn add_path<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        class_file::for_each_class_file(path, &mut |bytes| self.add_class(bytes).map(|_| ()))
+    }
+
+    /// Adds all class files of a JAR/WAR/EAR/ZIP archive, including nested archives.
+    pub fn add_archive<R: Read + Seek>(&mut self, r: R) -> Result<()> {
+        class_file::for_each_archived_class_file(r, &mut |bytes| self.add_class(bytes).map(|_| ()))
+    }
+}
+
+impl JacocoReport {
+    /// Checks which execution data entries still match the given classes.
+    pub fn check_class_ids<'a>(&'a self, classes: &ClassIds) -> ClassIdCheck<'a> {
+        let mut check = ClassIdCheck::default();
+        for execution_data in &self.execution_datas {
+            match classes.ids(&execution_data.name) {
+                Some(ids) if ids.contains(&execution_data.id) => {
+                    check.matching.push(execution_data)
+                }
+                Some(_) => check.stale.push(execution_data),
+                None => check.missing.push(execution_data),
+            }
+        }
+        check
+    }
+}
+
+impl<'a> ClassIdCheck<'a> {
+    pub fn matching(&self) -> &Vec<&'a ExecutionData> {
+        &self.matching
+    }
+
+    pub fn stale(&self) -> &Vec<&'a ExecutionData> {
+        &self.stale
+    }
+
+    pub fn missing(&self) -> &Vec<&'a ExecutionData> {
+        &self.missing
+    }
+}
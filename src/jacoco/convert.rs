@@ -0,0 +1,198 @@
+//! Conversion between JaCoCo's [`XmlReport`] and the format-neutral [`CoverageReport`].
+//!
+//! Execution data alone has no line information, [`XmlReport::from_classes`] turns it into a
+//! report that can be converted.
+
+use super::analyzer::line_counter;
+use super::xml_report::add_counters;
+use super::{
+    Counter, CounterEntity, Counters, LineCoverage, XmlClass, XmlMethod, XmlPackage, XmlReport,
+    XmlSourceFile,
+};
+use crate::coverage::{BranchCoverage, CoverageReport, FileCoverage, FunctionCoverage};
+
+use std::collections::BTreeMap;
+
+/// Files are identified by their package and source file name, e.g. `org/example/Foo.java`.
+/// JaCoCo only knows whether a line or method was executed, so hits are 0 or 1. Branches are
+/// numbered per line, covered ones first. Methods become functions named
+/// `<class>.<method><descriptor>`.
+impl From<&XmlReport> for CoverageReport {
+    fn from(report: &XmlReport) -> Self {
+        let mut coverage = CoverageReport::new(Some(report.name().clone()));
+        let mut packages: Vec<&XmlPackage> = report.packages().iter().collect();
+        let mut groups: Vec<_> = report.groups().iter().collect();
+        while let Some(group) = groups.pop() {
+            packages.extend(group.packages());
+            groups.extend(group.groups());
+        }
+
+        for package in packages {
+            for source_file in package.source_files() {
+                let file = coverage.file_mut(&source_path(package.name(), source_file.name()));
+                *file.package_mut() = Some(package.name().clone());
+                add_source_file(file, source_file);
+            }
+            for class in package.classes() {
+                let Some(source_file_name) = class.source_file_name() else {
+                    continue;
+                };
+                let file = coverage.file_mut(&source_path(package.name(), source_file_name));
+                for method in class.methods() {
+                    file.functions_mut().push(FunctionCoverage::new(
+                        format!("{}.{}{}", class.name(), method.name(), method.desc()),
+                        method.line(),
+                        None,
+                        method.counters().method().covered().into(),
+                    ));
+                }
+            }
+        }
+        coverage
+    }
+}
+
+fn source_path(package: &str, source_file: &str) -> String {
+    if package.is_empty() {
+        source_file.to_string()
+    } else {
+        format!("{package}/{source_file}")
+    }
+}
+
+fn add_source_file(file: &mut FileCoverage, source_file: &XmlSourceFile) {
+    for (&nr, line) in source_file.lines() {
+        if line.instructions().total() > 0 {
+            file.add_line_hits(nr, (line.instructions().covered() > 0).into());
+        }
+        let branches = line.branches();
+        for i in 0..branches.total() {
+            let taken = u64::from(i < branches.covered());
+            file.branches_mut()
+                .push(BranchCoverage::new(nr, 0, i, Some(taken)));
+        }
+    }
+}
+
+impl XmlReport {
+    /// Builds a report from format-neutral coverage. Every line counts as a single instruction,
+    /// functions named like the ones converted from JaCoCo become methods of their classes,
+    /// other functions methods of a class named after the file. The complexity isn't known and
+    /// stays 0.
+    pub fn from_coverage(coverage: &CoverageReport) -> Self {
+        let mut files_by_package: BTreeMap<String, Vec<(&str, &FileCoverage)>> = BTreeMap::new();
+        for (path, file) in coverage.files() {
+            let (dir, name) = path.rsplit_once('/').unwrap_or(("", path));
+            let package = file.package().cloned().unwrap_or_else(|| dir.to_string());
+            files_by_package
+                .entry(package)
+                .or_default()
+                .push((name, file));
+        }
+
+        let mut report = XmlReport::new(coverage.name().cloned().unwrap_or_default(), vec![]);
+        for (package_name, files) in files_by_package {
+            let mut classes: BTreeMap<String, XmlClass> = BTreeMap::new();
+            let mut source_files = vec![];
+            for (name, file) in files {
+                for function in file.functions() {
+                    let (class_name, method) = xml_method(&package_name, name, function);
+                    let class = classes.entry(class_name.clone()).or_insert_with(|| {
+                        XmlClass::new(
+                            class_name,
+                            Some(name.to_string()),
+                            vec![],
+                            Counters::default(),
+                        )
+                    });
+                    add_counters(class.counters_mut(), method.counters());
+                    class.methods_mut().push(method);
+                }
+                source_files.push(xml_source_file(name, file));
+            }
+
+            let classes: Vec<XmlClass> = classes
+                .into_values()
+                .map(|mut class| {
+                    let covered = class.counters().method().covered() > 0;
+                    *class.counters_mut().get_mut(CounterEntity::Class) =
+                        Counter::new((!covered).into(), covered.into());
+                    class
+                })
+                .collect();
+            let package = XmlPackage::new(package_name, classes, source_files);
+            add_counters(report.counters_mut(), package.counters());
+            report.packages_mut().push(package);
+        }
+        report
+    }
+}
+
+/// Class name and method of a function.
+fn xml_method(package: &str, file_name: &str, function: &FunctionCoverage) -> (String, XmlMethod) {
+    let covered = function.hits() > 0;
+    let mut counters = Counters::default();
+    *counters.get_mut(CounterEntity::Method) = Counter::new((!covered).into(), covered.into());
+
+    // `<class>.<method><descriptor>`, the class name may contain dots only if it's no VM name
+    let name = function.name();
+    let parsed = name.find('(').and_then(|desc_start| {
+        let (class, method) = name[..desc_start].rsplit_once('.')?;
+        Some((class, method, &name[desc_start..]))
+    });
+    let (class, method, desc) = match parsed {
+        Some((class, method, desc)) if !class.is_empty() && !method.is_empty() => {
+            (class.to_string(), method, desc)
+        }
+        _ => {
+            let stem = file_name.split('.').next().unwrap_or(file_name);
+            (source_path(package, stem), name.as_str(), "")
+        }
+    };
+    let method = XmlMethod::new(
+        method.to_string(),
+        desc.to_string(),
+        function.start_line(),
+        counters,
+    );
+    (class, method)
+}
+
+fn xml_source_file(name: &str, file: &FileCoverage) -> XmlSourceFile {
+    let mut lines: BTreeMap<u32, LineCoverage> = BTreeMap::new();
+    for (&nr, &hits) in file.lines() {
+        let covered = hits > 0;
+        let instructions = Counter::new((!covered).into(), covered.into());
+        lines.insert(nr, LineCoverage::new(instructions, Counter::default()));
+    }
+    for branch in file.branches() {
+        let covered = branch.taken().is_some_and(|taken| taken > 0);
+        let line = lines.entry(branch.line()).or_default();
+        let branches = line.branches();
+        *line = LineCoverage::new(
+            line.instructions(),
+            Counter::new(
+                branches.missed() + u32::from(!covered),
+                branches.covered() + u32::from(covered),
+            ),
+        );
+    }
+
+    let mut counters = Counters::default();
+    for line in lines.values() {
+        counters
+            .get_mut(CounterEntity::Instruction)
+            .increment(line.instructions());
+        counters
+            .get_mut(CounterEntity::Branch)
+            .increment(line.branches());
+    }
+    *counters.get_mut(CounterEntity::Line) = line_counter(&lines);
+    for function in file.functions() {
+        let covered = function.hits() > 0;
+        counters
+            .get_mut(CounterEntity::Method)
+            .increment(Counter::new((!covered).into(), covered.into()));
+    }
+    XmlSourceFile::new(name.to_string(), lines, counters)
+}
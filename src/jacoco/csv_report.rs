@@ -0,0 +1,247 @@
+use super::{ClassCoverage, Counter, CounterEntity, Counters, JacocoError, Result};
+
+use std::borrow::Cow;
+use std::io::{BufRead, Write};
+
+#[cfg(feature = "serialization")]
+use serde::{Deserialize, Serialize};
+
+/// Counters which have a missed and a covered column in the CSV report, in column order.
+const CSV_COUNTER_ENTITIES: [CounterEntity; 5] = [
+    CounterEntity::Instruction,
+    CounterEntity::Branch,
+    CounterEntity::Line,
+    CounterEntity::Complexity,
+    CounterEntity::Method,
+];
+
+/// JaCoCo's CSV report: one row of counters per class.
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct CsvReport {
+    rows: Vec<CsvRow>,
+}
+
+/// A single class of the CSV report. Names are Java names, e.g. `org.example` for the package
+/// (`default` for the default package) and `Outer.Inner` for a nested class. The CSV report
+/// has no CLASS counter, so it is always zero for rows read from a CSV file.
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct CsvRow {
+    group: String,
+    package: String,
+    class: String,
+    counters: Counters,
+}
+
+impl CsvReport {
+    pub fn new(rows: Vec<CsvRow>) -> Self {
+        Self { rows }
+    }
+
+    /// Builds a report with one row per analyzed class, all belonging to the given group.
+    pub fn from_classes(group: &str, classes: &[ClassCoverage]) -> Self {
+        let mut rows: Vec<CsvRow> = classes
+            .iter()
+            .map(|class| CsvRow {
+                group: group.to_string(),
+                package: match class.package_name() {
+                    "" => "default".to_string(),
+                    package => package.replace('/', "."),
+                },
+                class: class.name()[class.package_name().len()..]
+                    .trim_start_matches('/')
+                    .replace('$', "."),
+                counters: *class.counters(),
+            })
+            .collect();
+        rows.sort_by(|a, b| (&a.package, &a.class).cmp(&(&b.package, &b.class)));
+        Self { rows }
+    }
+
+    pub fn rows(&self) -> &Vec<CsvRow> {
+        &self.rows
+    }
+
+    pub fn rows_mut(&mut self) -> &mut Vec<CsvRow> {
+        &mut self.rows
+    }
+
+    /// # Errors
+    ///
+    /// Fails with `JacocoError::InvalidCsvHeader` if the first line isn't JaCoCo's CSV header
+    /// and with `JacocoError::InvalidCsvRow` if a row has the wrong number of columns or a
+    /// counter column is not a number.
+    pub fn from_buf_read<R: BufRead>(r: &mut R) -> Result<Self> {
+        let mut lines = r.lines();
+
+        let header = lines.next().transpose()?.unwrap_or_default();
+        if header.trim_end_matches('\r') != Self::header() {
+            return Err(JacocoError::InvalidCsvHeader(header).into());
+        }
+
+        let mut rows = Vec::new();
+        for (i, line) in lines.enumerate() {
+            let line = line?;
+            let line = line.trim_end_matches('\r');
+            if line.is_empty() {
+                continue;
+            }
+            // The header is line 1
+            let line_number = i + 2;
+            rows.push(CsvRow::parse(line).ok_or(JacocoError::InvalidCsvRow(line_number))?);
+        }
+
+        Ok(Self { rows })
+    }
+
+    /// Writes the report the same way JaCoCo's `CSVFormatter` does.
+    pub fn write_to<W: Write>(&self, w: &mut W) -> Result<()> {
+        writeln!(w, "{}", Self::header())?;
+        for row in &self.rows {
+            write!(
+                w,
+                "{},{},{}",
+                escape(&row.group),
+                escape(&row.package),
+                escape(&row.class)
+            )?;
+            for entity in CSV_COUNTER_ENTITIES {
+                let counter = row.counters.get(entity);
+                write!(w, ",{},{}", counter.missed(), counter.covered())?;
+            }
+            writeln!(w)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn header() -> String {
+        let mut header = String::from("GROUP,PACKAGE,CLASS");
+        for entity in CSV_COUNTER_ENTITIES {
+            header.push_str(&format!(",{0}_MISSED,{0}_COVERED", entity.as_str()));
+        }
+        header
+    }
+}
+
+impl CsvRow {
+    pub fn new(group: String, package: String, class: String, counters: Counters) -> Self {
+        Self {
+            group,
+            package,
+            class,
+            counters,
+        }
+    }
+
+    pub fn group(&self) -> &String {
+        &self.group
+    }
+
+    pub fn group_mut(&mut self) -> &mut String {
+        &mut self.group
+    }
+
+    pub fn package(&self) -> &String {
+        &self.package
+    }
+
+    pub fn package_mut(&mut self) -> &mut String {
+        &mut self.package
+    }
+
+    pub fn class(&self) -> &String {
+        &self.class
+    }
+
+    pub fn class_mut(&mut self) -> &mut String {
+        &mut self.class
+    }
+
+    pub fn counters(&self) -> &Counters {
+        &self.counters
+    }
+
+    pub fn counters_mut(&mut self) -> &mut Counters {
+        &mut self.counters
+    }
+
+    fn parse(line: &str) -> Option<Self> {
+        let values = split(line)?;
+        let [group, package, class, numbers @ ..] = values.as_slice() else {
+            return None;
+        };
+        if numbers.len() != 2 * CSV_COUNTER_ENTITIES.len() {
+            return None;
+        }
+
+        let mut counters = Counters::default();
+        for (entity, counter) in CSV_COUNTER_ENTITIES.into_iter().zip(numbers.chunks(2)) {
+            *counters.get_mut(entity) =
+                Counter::new(counter[0].parse().ok()?, counter[1].parse().ok()?);
+        }
+
+        Some(Self {
+            group: group.clone(),
+            package: package.clone(),
+            class: class.clone(),
+            counters,
+        })
+    }
+}
+
+/// Quotes values containing a comma or a quote, doubling the quotes, like JaCoCo's
+/// `DelimitedWriter`.
+fn escape(value: &str) -> Cow<'_, str> {
+    if value.contains([',', '"']) {
+        Cow::Owned(format!("\"{}\"", value.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+/// Splits a CSV line into its unescaped values. Returns `None` for an unterminated quote.
+fn split(line: &str) -> Option<Vec<String>> {
+    let mut values = Vec::new();
+    let mut value = String::new();
+    let mut chars = line.chars().peekable();
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                value.push('"');
+            }
+            '"' if quoted => quoted = false,
+            '"' if value.is_empty() => quoted = true,
+            ',' if !quoted => values.push(std::mem::take(&mut value)),
+            c => value.push(c),
+        }
+    }
+    if quoted {
+        return None;
+    }
+    values.push(value);
+    Some(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_report_round_trip_is_byte_exact() {
+        let bytes = include_bytes!("../../examples/files/jacoco.csv");
+        let report = CsvReport::from_buf_read(&mut bytes.as_slice()).unwrap();
+        let mut written = vec![];
+        report.write_to(&mut written).unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&written),
+            String::from_utf8_lossy(bytes)
+        );
+        assert_eq!(
+            CsvReport::from_buf_read(&mut written.as_slice()).unwrap(),
+            report
+        );
+    }
+}
@@ -4,14 +4,36 @@ use thiserror::Error;
 pub enum JacocoError {
     #[error("Invalid header. Expected: 0xC0C0, got {0:#X}")]
     WrongMagicHeader(i16),
-    #[error("Invalid format version. Expected: 0x1007, got: {0:#X}")]
+    #[error("Invalid format version. Expected one of the following: 0x1006, 0x1007, got: {0:#X}")]
     WrongFormatVersion(i16),
+    #[error("Incompatible format versions: {0:#X} and {1:#X}")]
+    IncompatibleFormatVersions(i16, i16),
     #[error("Invalid block type. Expected one of the following: 0x01, 0x10, 0x11, got: {0:#x}")]
     WrongBlockType(u8),
     #[error("Invalid execution data file")]
     InvalidFile,
+    #[error("Unexpected remote control block, expected command confirmation")]
+    UnexpectedRemoteBlock,
+    #[error("Block of type {block_type:#04x} at byte offset {offset} is truncated")]
+    TruncatedBlock { block_type: u8, offset: u64 },
+    #[error("Invalid modified UTF-8 string")]
+    InvalidModifiedUtf8,
     #[error("Invalid unix timestamp: {0}")]
     InvalidTimestamp(i64),
+    #[error("Invalid class file: {0}")]
+    InvalidClassFile(String),
+    #[error("Class {0} uses subroutines (jsr/ret), which are not supported")]
+    UnsupportedSubroutines(String),
+    #[error("Invalid counter type: {0}")]
+    InvalidCounterEntity(String),
+    #[error("Invalid XML report: missing or invalid attribute {attribute:?} of <{element}>")]
+    InvalidXmlAttribute { element: String, attribute: String },
+    #[error("Invalid XML report: no <report> element")]
+    MissingXmlReportElement,
+    #[error("Invalid CSV report header: {0}")]
+    InvalidCsvHeader(String),
+    #[error("Invalid CSV report row at line {0}")]
+    InvalidCsvRow(usize),
     #[error("Different ids: ({0:016x} and {1:016x})")]
     IllegalStateDifferentIds(i64, i64),
     #[error("Different class names {0} and {1} for id {2}")]
@@ -1,22 +1,59 @@
 pub(super) use super::{Error, Result};
+pub use analyzer::{ClassCoverage, Counter, CounterEntity, Counters, LineCoverage, MethodCoverage};
+pub use class_id::{ClassIdCheck, ClassIds, class_id};
+pub use csv_report::{CsvReport, CsvRow};
 pub use error::JacocoError;
+pub use reader::ExecutionDataReader;
+pub use remote::{ExecutionDataServer, RemoteConnection, RemoteSignal};
+pub use visitor::{ExecutionDataVisitor, SessionInfoVisitor};
+pub use writer::ExecutionDataWriter;
+pub use xml_report::{XmlClass, XmlGroup, XmlMethod, XmlPackage, XmlReport, XmlSourceFile};
 
 use chrono::{DateTime, Datelike, Local};
 use core::fmt::Display;
+use std::collections::{HashMap, HashSet, hash_map::Entry};
 
 #[cfg(feature = "serialization")]
 use serde::{Deserialize, Serialize};
 
+mod analyzer;
+mod class_file;
+mod class_id;
+mod convert;
+mod csv_report;
 mod error;
 mod reader;
+mod remote;
+mod visitor;
+mod writer;
+mod xml_report;
 
 #[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
 pub struct JacocoReport {
+    version: Option<FormatVersion>,
     session_infos: Vec<SessionInfo>,
     execution_datas: Vec<ExecutionData>,
 }
 
+/// Version of the binary `.exec` format, as stored in the header block.
+///
+/// Both versions share one block layout: JaCoCo's `ExecutionDataWriter` and
+/// `ExecutionDataReader` (`org.jacoco.core.data`) write and read the header, session info and
+/// execution data blocks the same way in 0.7.4 and 0.7.5. The 0.7.5 release only changed the
+/// `FORMAT_VERSION` constant, because the way probes are inserted into classes changed, see its
+/// change log: "The exec file version has been updated and is not compatible with previous
+/// versions". Probe arrays written with different versions therefore can't be merged.
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub enum FormatVersion {
+    /// Format written by JaCoCo agents before 0.7.5
+    V1006 = 0x1006,
+    /// Format written by JaCoCo agents since 0.7.5
+    #[default]
+    V1007 = 0x1007,
+}
+
 #[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
 pub(super) enum BlockType {
@@ -25,6 +62,14 @@ pub(super) enum BlockType {
     ExecutionData = 0x11,
 }
 
+/// A single session info or execution data block, as yielded by [`ExecutionDataReader`].
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum ExecutionDataBlock {
+    SessionInfo(SessionInfo),
+    ExecutionData(ExecutionData),
+}
+
 /// Data object describing a session which was the source of execution data.
 #[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
@@ -54,11 +99,22 @@ pub struct ExecutionData {
 impl JacocoReport {
     pub fn new(session_infos: Vec<SessionInfo>, execution_datas: Vec<ExecutionData>) -> Self {
         Self {
+            version: Some(FormatVersion::default()),
             session_infos,
             execution_datas,
         }
     }
 
+    /// Format version of the header the report was read from, and will be written with.
+    /// `None` if the input had no header, the current version for new reports.
+    pub fn version(&self) -> Option<FormatVersion> {
+        self.version
+    }
+
+    pub fn version_mut(&mut self) -> &mut Option<FormatVersion> {
+        &mut self.version
+    }
+
     pub fn session_infos(&self) -> &Vec<SessionInfo> {
         &self.session_infos
     }
@@ -93,15 +149,33 @@ impl ExecutionData {
         &self.probes
     }
 
-    pub fn covered_lines(&self) -> usize {
+    /// Number of executed probes. See [`ClassCoverage`] for coverage of actual lines.
+    pub fn covered_probes(&self) -> usize {
         self.probes().iter().filter(|probe| probe == &&true).count()
     }
+
+    #[deprecated(note = "counts probes, not lines, use `covered_probes` or `ClassCoverage`")]
+    pub fn covered_lines(&self) -> usize {
+        self.covered_probes()
+    }
 }
 
 impl SessionInfo {
     pub fn new(id: String, start: DateTime<Local>, dump: DateTime<Local>) -> Self {
         Self { id, start, dump }
     }
+
+    pub fn id(&self) -> &String {
+        &self.id
+    }
+
+    pub fn start(&self) -> &DateTime<Local> {
+        &self.start
+    }
+
+    pub fn dump(&self) -> &DateTime<Local> {
+        &self.dump
+    }
 }
 
 impl JacocoReport {
@@ -116,39 +190,132 @@ impl JacocoReport {
     //
     ///// Block identifier for execution data of a single class.
     //pub(super) const BLOCK_EXECUTIONDATA: i8 = 0x11;
+}
 
-    pub(super) const FORMAT_VERSION: i16 = 0x1007;
+impl TryFrom<i16> for FormatVersion {
+    type Error = JacocoError;
+
+    fn try_from(value: i16) -> core::result::Result<Self, Self::Error> {
+        match value {
+            0x1006 => Ok(Self::V1006),
+            0x1007 => Ok(Self::V1007),
+            _ => Err(JacocoError::WrongFormatVersion(value)),
+        }
+    }
 }
 
 impl ExecutionData {
-    pub fn try_merge(self, other: Self) -> core::result::Result<Self, JacocoError> {
+    pub fn try_merge(mut self, other: Self) -> core::result::Result<Self, JacocoError> {
+        self.merge(&other)?;
+        Ok(self)
+    }
+
+    /// Merges the probes of `other` into this instance. A probe is considered executed if it
+    /// is executed in either of the two instances.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `IllegalState*` error if `other` has a different id, class name or number
+    /// of probes. In that case `self` is left unchanged.
+    pub fn merge(&mut self, other: &Self) -> core::result::Result<(), JacocoError> {
         if self.id != other.id {
             return Err(JacocoError::IllegalStateDifferentIds(self.id, other.id));
         }
 
         if self.name != other.name {
             return Err(JacocoError::IllegalStateDifferentNames(
-                self.name, other.name, self.id,
+                self.name.clone(),
+                other.name.clone(),
+                self.id,
             ));
         }
 
-        if self.probes.len() != other.probes().len() {
+        if self.probes.len() != other.probes.len() {
             return Err(JacocoError::IllegalStateIncompatibleProbes(
-                self.name, self.id,
+                self.name.clone(),
+                self.id,
             ));
         }
 
-        let probes: Vec<bool> = self
-            .probes
-            .iter()
+        self.probes
+            .iter_mut()
             .zip(other.probes.iter())
-            .map(|(&old, &new)| old || new)
-            .collect();
+            .for_each(|(old, &new)| *old |= new);
+
+        Ok(())
+    }
+}
+
+impl JacocoReport {
+    /// Merges two reports the same way JaCoCo's `ExecutionDataStore` and `SessionInfoStore` do.
+    /// See [`JacocoReport::try_merge_all`] for details.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `IllegalState*` error if two execution data entries with the same class
+    /// id have different class names or numbers of probes, and
+    /// `JacocoError::IncompatibleFormatVersions` if the reports have different format versions
+    pub fn try_merge(self, other: Self) -> Result<Self> {
+        Self::try_merge_all([self, other])
+    }
+
+    /// Merges any number of reports into a single one.
+    ///
+    /// Execution data entries are grouped by class id and their probes are OR-ed together, in
+    /// the order in which the classes were first seen. Session infos are deduplicated and sorted
+    /// by their start time stamp.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `IllegalState*` error if two execution data entries with the same class
+    /// id have different class names or numbers of probes, and
+    /// `JacocoError::IncompatibleFormatVersions` if the reports have different format versions.
+    /// Reports without a version can be merged with reports of any version.
+    pub fn try_merge_all<I: IntoIterator<Item = Self>>(reports: I) -> Result<Self> {
+        let mut version = None;
+        let mut session_infos = vec![];
+        let mut seen_session_infos = HashSet::new();
+        let mut execution_datas: Vec<ExecutionData> = vec![];
+        let mut index_by_id: HashMap<i64, usize> = HashMap::new();
+
+        for report in reports {
+            match (version, report.version) {
+                (Some(v), Some(report_version)) if v != report_version => {
+                    return Err(JacocoError::IncompatibleFormatVersions(
+                        v as i16,
+                        report_version as i16,
+                    )
+                    .into());
+                }
+                (None, report_version) => version = report_version,
+                _ => {}
+            }
+
+            for session_info in report.session_infos {
+                if seen_session_infos.insert(session_info.clone()) {
+                    session_infos.push(session_info);
+                }
+            }
+
+            for execution_data in report.execution_datas {
+                match index_by_id.entry(execution_data.id) {
+                    Entry::Occupied(occ) => {
+                        execution_datas[*occ.get()].merge(&execution_data)?;
+                    }
+                    Entry::Vacant(vac) => {
+                        vac.insert(execution_datas.len());
+                        execution_datas.push(execution_data);
+                    }
+                }
+            }
+        }
+
+        session_infos.sort_by_key(|session_info| session_info.start);
 
         Ok(Self {
-            id: self.id,
-            name: self.name,
-            probes,
+            version,
+            session_infos,
+            execution_datas,
         })
     }
 }
@@ -206,11 +373,14 @@ impl Display for JacocoReport {
 
         for execution_data in &self.execution_datas {
             let id = execution_data.id;
-            let covered_lines = execution_data.covered_lines();
+            let covered_probes = execution_data.covered_probes();
             let probes_len = execution_data.probes.len();
             let name = &execution_data.name;
 
-            writeln!(f, "{id:016x}  {covered_lines:3} of {probes_len:3}   {name}",)?
+            writeln!(
+                f,
+                "{id:016x}  {covered_probes:3} of {probes_len:3}   {name}",
+            )?
         }
         Ok(())
     }
@@ -1,82 +1,233 @@
-use super::{BlockType, Error, ExecutionData, JacocoError, JacocoReport, Result, SessionInfo};
+use super::{
+    BlockType, Error, ExecutionData, ExecutionDataBlock, ExecutionDataVisitor, FormatVersion,
+    JacocoError, JacocoReport, Result, SessionInfo, SessionInfoVisitor,
+};
 
 use byteorder::{BigEndian, ReadBytesExt};
 use chrono::{DateTime, Local};
 use std::io::{ErrorKind, Read};
 
-impl JacocoReport {
-    pub fn from_read<R: Read>(r: &mut R) -> Result<Self> {
-        let mut is_first_block = false;
+/// Streaming reader for the binary `.exec` format. Blocks are decoded one by one from the
+/// underlying [`Read`], so only a single session info or execution data entry is held in
+/// memory at a time.
+///
+/// Blocks can either be pushed into a visitor with [`ExecutionDataReader::read`] or pulled
+/// with the [`Iterator`] implementation.
+///
+/// By default the reader is lenient: it stops silently at a truncated block and skips blocks
+/// it can't decode. Header blocks are never skipped, an unknown format version or a header of
+/// a different version than the first one is an error in both modes. See
+/// [`ExecutionDataReader::strict`] for a mode that reports the other errors too.
+pub struct ExecutionDataReader<R: Read> {
+    r: CountingRead<R>,
+    strict: bool,
+    version: Option<FormatVersion>,
+    is_first_block: bool,
+    finished: bool,
+}
 
-        let mut session_infos = vec![];
-        let mut execution_datas = vec![];
+impl<R: Read> ExecutionDataReader<R> {
+    pub fn new(r: R) -> Self {
+        Self {
+            r: CountingRead {
+                inner: r,
+                offset: 0,
+            },
+            strict: false,
+            version: None,
+            is_first_block: true,
+            finished: false,
+        }
+    }
 
-        loop {
-            let Ok(i) = Self::read(r) else { break };
-            let block_type = BlockType::try_from(i)?;
+    /// In strict mode the reader fails with `JacocoError::TruncatedBlock` if the input ends in
+    /// the middle of a block, with `JacocoError::InvalidFile` if the first block is not a
+    /// header, and propagates every other decoding or I/O error instead of skipping it.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
 
-            if is_first_block && block_type != BlockType::Header {
-                return Err(JacocoError::InvalidFile.into());
+    /// Reads all remaining blocks and reports them to `visitor`.
+    pub fn read<V: SessionInfoVisitor + ExecutionDataVisitor>(
+        &mut self,
+        visitor: &mut V,
+    ) -> Result<()> {
+        while let Some(block) = self.next_block()? {
+            match block {
+                ExecutionDataBlock::SessionInfo(session_info) => {
+                    visitor.visit_session_info(session_info)?
+                }
+                ExecutionDataBlock::ExecutionData(execution_data) => {
+                    visitor.visit_class_execution(execution_data)?
+                }
             }
-            is_first_block = false;
+        }
+        Ok(())
+    }
+
+    /// Format version of the header blocks read so far, `None` if no header has been read yet.
+    /// All header blocks of a stream must have the same version.
+    pub fn version(&self) -> Option<FormatVersion> {
+        self.version
+    }
 
-            let Err(Error::Io(io_error)) =
-                Self::read_block(r, block_type, &mut session_infos, &mut execution_datas)
-            else {
-                continue;
+    /// Number of bytes consumed from the underlying reader so far.
+    pub fn offset(&self) -> u64 {
+        self.r.offset
+    }
+
+    pub fn into_inner(self) -> R {
+        self.r.inner
+    }
+
+    fn next_block(&mut self) -> Result<Option<ExecutionDataBlock>> {
+        while !self.finished {
+            let offset = self.r.offset;
+            let i = match JacocoReport::read(&mut self.r) {
+                Ok(i) => i,
+                Err(io_error) => {
+                    self.finished = true;
+                    if self.strict && io_error.kind() != ErrorKind::UnexpectedEof {
+                        return Err(io_error.into());
+                    }
+                    break;
+                }
+            };
+            let block_type = match BlockType::try_from(i) {
+                Ok(block_type) => block_type,
+                Err(e) => {
+                    self.finished = true;
+                    return Err(e.into());
+                }
             };
-            if io_error.kind() == ErrorKind::UnexpectedEof {
-                break;
+
+            if self.strict && self.is_first_block && block_type != BlockType::Header {
+                self.finished = true;
+                return Err(JacocoError::InvalidFile.into());
+            }
+            self.is_first_block = false;
+
+            match JacocoReport::read_block(&mut self.r, block_type, &mut self.version) {
+                Ok(Some(block)) => return Ok(Some(block)),
+                Ok(None) => continue,
+                Err(Error::Io(io_error)) if io_error.kind() == ErrorKind::UnexpectedEof => {
+                    self.finished = true;
+                    if self.strict {
+                        return Err(JacocoError::TruncatedBlock {
+                            block_type: i,
+                            offset,
+                        }
+                        .into());
+                    }
+                }
+                Err(
+                    e @ Error::Jacoco(
+                        JacocoError::WrongMagicHeader(_)
+                        | JacocoError::WrongFormatVersion(_)
+                        | JacocoError::IncompatibleFormatVersions(..),
+                    ),
+                ) => {
+                    self.finished = true;
+                    return Err(e);
+                }
+                Err(e) if self.strict => {
+                    self.finished = true;
+                    return Err(e);
+                }
+                Err(_) => continue,
             }
         }
+        Ok(None)
+    }
+}
 
-        let jacoco_report = Self {
-            session_infos,
-            execution_datas,
-        };
+/// Keeps track of the number of bytes read, so errors can point at the offending block.
+struct CountingRead<R: Read> {
+    inner: R,
+    offset: u64,
+}
+
+impl<R: Read> Read for CountingRead<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.offset += n as u64;
+        Ok(n)
+    }
+}
+
+impl<R: Read> Iterator for ExecutionDataReader<R> {
+    type Item = Result<ExecutionDataBlock>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_block().transpose()
+    }
+}
+
+impl JacocoReport {
+    pub fn from_read<R: Read>(r: &mut R) -> Result<Self> {
+        let mut jacoco_report = Self::new(vec![], vec![]);
+        let mut reader = ExecutionDataReader::new(r);
+        reader.read(&mut jacoco_report)?;
+        jacoco_report.version = reader.version();
+        Ok(jacoco_report)
+    }
+
+    /// Same as [`JacocoReport::from_read`], but fails on truncated or corrupted input instead
+    /// of returning a partial report. See [`ExecutionDataReader::strict`].
+    pub fn from_read_strict<R: Read>(r: &mut R) -> Result<Self> {
+        let mut jacoco_report = Self::new(vec![], vec![]);
+        let mut reader = ExecutionDataReader::new(r).strict(true);
+        reader.read(&mut jacoco_report)?;
+        jacoco_report.version = reader.version();
         Ok(jacoco_report)
     }
 
-    fn read_block<R: Read>(
+    pub(super) fn read_block<R: Read>(
         r: &mut R,
         block_type: BlockType,
-        session_infos: &mut Vec<SessionInfo>,
-        execution_datas: &mut Vec<ExecutionData>,
-    ) -> Result<()> {
-        match block_type {
+        version: &mut Option<FormatVersion>,
+    ) -> Result<Option<ExecutionDataBlock>> {
+        let block = match block_type {
             BlockType::Header => {
-                Self::read_header(r)?;
+                let header_version = Self::read_header(r)?;
+                match *version {
+                    Some(v) if v != header_version => {
+                        return Err(JacocoError::IncompatibleFormatVersions(
+                            v as i16,
+                            header_version as i16,
+                        )
+                        .into());
+                    }
+                    _ => *version = Some(header_version),
+                }
+                None
             }
             BlockType::SessionInfo => {
-                let session_info = Self::read_session_info(r)?;
-                session_infos.push(session_info);
-            }
-            BlockType::ExecutionData => {
-                let execution_data = Self::read_execution_data(r)?;
-                execution_datas.push(execution_data);
+                Some(ExecutionDataBlock::SessionInfo(Self::read_session_info(r)?))
             }
+            BlockType::ExecutionData => Some(ExecutionDataBlock::ExecutionData(
+                Self::read_execution_data(r)?,
+            )),
         };
 
-        Ok(())
+        Ok(block)
     }
 
-    fn read_header<R: Read>(r: &mut R) -> Result<()> {
+    fn read_header<R: Read>(r: &mut R) -> Result<FormatVersion> {
         let first_char = Self::read_char(r)?;
         if first_char != Self::MAGIC_NUMBER {
             return Err(JacocoError::WrongMagicHeader(first_char).into());
         }
 
         let second_char = Self::read_char(r)?;
-        if second_char != Self::FORMAT_VERSION {
-            return Err(JacocoError::WrongFormatVersion(second_char).into());
-        }
+        let version = FormatVersion::try_from(second_char)?;
 
-        Ok(())
+        Ok(version)
     }
 
     fn read_session_info<R: Read>(r: &mut R) -> Result<SessionInfo> {
         let id = Self::read_utf8(r)?;
-        // FIXME: Dont unwrap
         let start_unix_timestamp = Self::read_long(r)?;
         let dump_unix_timestamp = Self::read_long(r)?;
 
@@ -85,7 +236,7 @@ impl JacocoReport {
             .into();
 
         let dump: DateTime<Local> = DateTime::from_timestamp_millis(dump_unix_timestamp)
-            .ok_or(JacocoError::InvalidTimestamp(start_unix_timestamp))?
+            .ok_or(JacocoError::InvalidTimestamp(dump_unix_timestamp))?
             .into();
 
         let session_info = SessionInfo { id, start, dump };
@@ -125,7 +276,7 @@ impl JacocoReport {
         Ok(value)
     }
 
-    fn read<R: Read>(r: &mut R) -> std::io::Result<u8> {
+    pub(super) fn read<R: Read>(r: &mut R) -> std::io::Result<u8> {
         r.read_u8()
     }
 
@@ -137,12 +288,41 @@ impl JacocoReport {
         r.read_i64::<BigEndian>()
     }
 
+    /// Reads a string in Java's modified UTF-8, the same way `DataInputStream.readUTF` does.
+    ///
+    /// # Errors
+    ///
+    /// Will return `JacocoError::InvalidModifiedUtf8` if the bytes are malformed or contain an
+    /// unpaired surrogate, which Java strings can hold but Rust strings can't
     fn read_utf8<R: Read>(r: &mut R) -> Result<String> {
         let length = r.read_u16::<BigEndian>()?;
-        let mut full_string_buffer = vec![0; length.into()];
-        r.read_exact(&mut full_string_buffer)?;
+        let mut bytes = vec![0; length.into()];
+        r.read_exact(&mut bytes)?;
 
-        let utf8_string = String::from_utf8(full_string_buffer)?;
-        Ok(utf8_string)
+        let continuation = |at: usize| -> Result<u16> {
+            match bytes.get(at) {
+                Some(&b) if b & 0xC0 == 0x80 => Ok(u16::from(b & 0x3F)),
+                _ => Err(JacocoError::InvalidModifiedUtf8.into()),
+            }
+        };
+        let mut units = Vec::with_capacity(bytes.len());
+        let mut i = 0;
+        while i < bytes.len() {
+            let b = bytes[i];
+            let (unit, length) = match b >> 4 {
+                0..=7 => (u16::from(b), 1),
+                12 | 13 => (u16::from(b & 0x1F) << 6 | continuation(i + 1)?, 2),
+                14 => (
+                    u16::from(b & 0x0F) << 12 | continuation(i + 1)? << 6 | continuation(i + 2)?,
+                    3,
+                ),
+                _ => return Err(JacocoError::InvalidModifiedUtf8.into()),
+            };
+            units.push(unit);
+            i += length;
+        }
+        char::decode_utf16(units)
+            .collect::<std::result::Result<String, _>>()
+            .map_err(|_| JacocoError::InvalidModifiedUtf8.into())
     }
 }
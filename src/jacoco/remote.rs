@@ -0,0 +1,229 @@
+use super::{
+    BlockType, Error, ExecutionData, ExecutionDataBlock, ExecutionDataVisitor, FormatVersion,
+    JacocoError, JacocoReport, Result, SessionInfo, SessionInfoVisitor,
+};
+
+use byteorder::{ReadBytesExt, WriteBytesExt};
+use std::io::{ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+
+/// Block identifier to confirm successful command execution.
+const BLOCK_CMDOK: u8 = 0x20;
+
+/// Block identifier for dump command.
+const BLOCK_CMDDUMP: u8 = 0x40;
+
+/// What stopped [`RemoteConnection::read`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum RemoteSignal {
+    /// The peer confirmed that the last command was executed. For a dump command all
+    /// execution data has been received at this point.
+    CmdOk,
+    /// The peer requests a dump of its execution data, optionally followed by a reset.
+    DumpCommand { dump: bool, reset: bool },
+    /// The peer closed the connection.
+    EndOfStream,
+}
+
+/// One end of a connection speaking JaCoCo's remote control protocol, the counterpart of
+/// `RemoteControlWriter` and `RemoteControlReader` used by agents started with
+/// `output=tcpserver` or `output=tcpclient`.
+///
+/// Both peers start by sending a header block. Afterwards the exec file blocks are exchanged,
+/// extended by a dump command block (`0x40`) and a command confirmation block (`0x20`).
+pub struct RemoteConnection<S: Read + Write> {
+    stream: S,
+    version: Option<FormatVersion>,
+    is_first_block: bool,
+}
+
+impl RemoteConnection<TcpStream> {
+    /// Connects to an agent running with `output=tcpserver`.
+    pub fn connect<A: ToSocketAddrs>(addr: A) -> Result<Self> {
+        Self::new(TcpStream::connect(addr)?)
+    }
+}
+
+impl<S: Read + Write> RemoteConnection<S> {
+    pub fn new(stream: S) -> Result<Self> {
+        Self::with_version(stream, FormatVersion::default())
+    }
+
+    /// Creates a connection whose header block declares the given format version.
+    pub fn with_version(mut stream: S, version: FormatVersion) -> Result<Self> {
+        JacocoReport::write_header(&mut stream, version)?;
+        stream.flush()?;
+        Ok(Self {
+            stream,
+            version: None,
+            is_first_block: true,
+        })
+    }
+
+    /// Format version declared by the peer, `None` if its header has not been read yet.
+    pub fn version(&self) -> Option<FormatVersion> {
+        self.version
+    }
+
+    /// Asks the peer to dump its execution data and/or reset it.
+    pub fn send_dump_command(&mut self, dump: bool, reset: bool) -> Result<()> {
+        self.stream.write_u8(BLOCK_CMDDUMP)?;
+        self.stream.write_u8(dump.into())?;
+        self.stream.write_u8(reset.into())?;
+        self.stream.flush()?;
+        Ok(())
+    }
+
+    /// Confirms that the last command of the peer was executed.
+    pub fn send_cmd_ok(&mut self) -> Result<()> {
+        self.stream.write_u8(BLOCK_CMDOK)?;
+        self.stream.flush()?;
+        Ok(())
+    }
+
+    /// Reads blocks and reports execution data to `visitor` until a command block is
+    /// received or the peer closes the connection.
+    ///
+    /// # Errors
+    ///
+    /// Will return `JacocoError::InvalidFile` if the peer doesn't start with a header block, and
+    /// any decoding or I/O error. Unlike the exec file reader nothing is skipped.
+    pub fn read<V: SessionInfoVisitor + ExecutionDataVisitor>(
+        &mut self,
+        visitor: &mut V,
+    ) -> Result<RemoteSignal> {
+        loop {
+            let i = match JacocoReport::read(&mut self.stream) {
+                Ok(i) => i,
+                Err(io_error) if io_error.kind() == ErrorKind::UnexpectedEof => {
+                    return Ok(RemoteSignal::EndOfStream);
+                }
+                Err(io_error) => return Err(io_error.into()),
+            };
+
+            if self.is_first_block && i != BlockType::Header as u8 {
+                return Err(JacocoError::InvalidFile.into());
+            }
+            self.is_first_block = false;
+
+            match i {
+                BLOCK_CMDOK => return Ok(RemoteSignal::CmdOk),
+                BLOCK_CMDDUMP => {
+                    let dump = self.stream.read_u8()? != 0;
+                    let reset = self.stream.read_u8()? != 0;
+                    return Ok(RemoteSignal::DumpCommand { dump, reset });
+                }
+                _ => {}
+            }
+
+            let block_type = BlockType::try_from(i)?;
+            match JacocoReport::read_block(&mut self.stream, block_type, &mut self.version)? {
+                Some(ExecutionDataBlock::SessionInfo(session_info)) => {
+                    visitor.visit_session_info(session_info)?
+                }
+                Some(ExecutionDataBlock::ExecutionData(execution_data)) => {
+                    visitor.visit_class_execution(execution_data)?
+                }
+                None => {}
+            }
+        }
+    }
+
+    /// Requests a dump from the peer and collects the execution data it sends back.
+    pub fn dump(&mut self, reset: bool) -> Result<JacocoReport> {
+        self.send_dump_command(true, reset)?;
+
+        let mut jacoco_report = JacocoReport::new(vec![], vec![]);
+        match self.read(&mut jacoco_report)? {
+            RemoteSignal::CmdOk => {
+                jacoco_report.version = self.version;
+                Ok(jacoco_report)
+            }
+            RemoteSignal::DumpCommand { .. } => Err(JacocoError::UnexpectedRemoteBlock.into()),
+            RemoteSignal::EndOfStream => Err(std::io::Error::from(ErrorKind::UnexpectedEof).into()),
+        }
+    }
+
+    /// Reads until the peer closes the connection and returns everything it sent. A peer that
+    /// closes without reading our header causes a connection reset, which counts as the end of
+    /// the stream if the peer confirmed everything it sent before.
+    pub fn collect(&mut self) -> Result<JacocoReport> {
+        let mut jacoco_report = JacocoReport::new(vec![], vec![]);
+        let mut confirmed = false;
+        loop {
+            let signal = match self.read(&mut jacoco_report) {
+                Ok(signal) => signal,
+                Err(Error::Io(io_error))
+                    if confirmed && io_error.kind() == ErrorKind::ConnectionReset =>
+                {
+                    break;
+                }
+                Err(e) => return Err(e),
+            };
+            confirmed = signal == RemoteSignal::CmdOk;
+            match signal {
+                RemoteSignal::CmdOk => continue,
+                RemoteSignal::DumpCommand { .. } => {
+                    return Err(JacocoError::UnexpectedRemoteBlock.into());
+                }
+                RemoteSignal::EndOfStream => break,
+            }
+        }
+        jacoco_report.version = self.version;
+        Ok(jacoco_report)
+    }
+
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+}
+
+impl<S: Read + Write> SessionInfoVisitor for RemoteConnection<S> {
+    fn visit_session_info(&mut self, session_info: SessionInfo) -> Result<()> {
+        JacocoReport::write_session_info(&mut self.stream, &session_info)
+    }
+}
+
+impl<S: Read + Write> ExecutionDataVisitor for RemoteConnection<S> {
+    fn visit_class_execution(&mut self, execution_data: ExecutionData) -> Result<()> {
+        JacocoReport::write_execution_data(&mut self.stream, &execution_data)
+    }
+}
+
+/// Server for agents running with `output=tcpclient`, which connect on startup and send their
+/// execution data when the JVM shuts down.
+pub struct ExecutionDataServer {
+    listener: TcpListener,
+}
+
+impl ExecutionDataServer {
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        Ok(Self { listener })
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// Waits for the next agent to connect. The returned connection can be used to request
+    /// dumps while the agent is running.
+    pub fn accept(&self) -> Result<RemoteConnection<TcpStream>> {
+        let (stream, _) = self.listener.accept()?;
+        RemoteConnection::new(stream)
+    }
+
+    /// Waits for the next agent to connect and collects all execution data it sends until it
+    /// disconnects.
+    pub fn collect_next(&self) -> Result<JacocoReport> {
+        self.accept()?.collect()
+    }
+}
+
+impl JacocoReport {
+    /// Connects to an agent running with `output=tcpserver` and requests a dump of its
+    /// execution data, optionally resetting it afterwards.
+    pub fn dump_from_agent<A: ToSocketAddrs>(addr: A, reset: bool) -> Result<Self> {
+        RemoteConnection::connect(addr)?.dump(reset)
+    }
+}
@@ -0,0 +1,30 @@
+use super::{ExecutionData, JacocoReport, Result, SessionInfo};
+
+/// Interface for data output of collected session information. Mirrors JaCoCo's
+/// `ISessionInfoVisitor`.
+pub trait SessionInfoVisitor {
+    /// Provides session information for the subsequent execution data calls. In case of merged
+    /// sessions this method might be called multiple times.
+    fn visit_session_info(&mut self, session_info: SessionInfo) -> Result<()>;
+}
+
+/// Interface for data output of collected execution data. Mirrors JaCoCo's
+/// `IExecutionDataVisitor`.
+pub trait ExecutionDataVisitor {
+    /// Provides execution data for a class.
+    fn visit_class_execution(&mut self, execution_data: ExecutionData) -> Result<()>;
+}
+
+impl SessionInfoVisitor for JacocoReport {
+    fn visit_session_info(&mut self, session_info: SessionInfo) -> Result<()> {
+        self.session_infos.push(session_info);
+        Ok(())
+    }
+}
+
+impl ExecutionDataVisitor for JacocoReport {
+    fn visit_class_execution(&mut self, execution_data: ExecutionData) -> Result<()> {
+        self.execution_datas.push(execution_data);
+        Ok(())
+    }
+}
@@ -0,0 +1,764 @@
+use super::analyzer::line_counter;
+use super::{
+    ClassCoverage, Counter, CounterEntity, Counters, JacocoError, LineCoverage, Result, SessionInfo,
+};
+use crate::xml::{XmlElement, XmlElementReader, escape};
+
+use chrono::{DateTime, Local};
+use std::collections::BTreeMap;
+use std::io::{BufRead, Write};
+use std::str::FromStr;
+
+#[cfg(feature = "serialization")]
+use serde::{Deserialize, Serialize};
+
+/// JaCoCo's XML report (`jacoco.xml`), following JaCoCo's `report.dtd`.
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct XmlReport {
+    name: String,
+    session_infos: Vec<SessionInfo>,
+    groups: Vec<XmlGroup>,
+    packages: Vec<XmlPackage>,
+    counters: Counters,
+}
+
+/// A group of bundles, e.g. a module of a multi-module build.
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct XmlGroup {
+    name: String,
+    groups: Vec<XmlGroup>,
+    packages: Vec<XmlPackage>,
+    counters: Counters,
+}
+
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct XmlPackage {
+    /// VM name of the package
+    name: String,
+    classes: Vec<XmlClass>,
+    source_files: Vec<XmlSourceFile>,
+    counters: Counters,
+}
+
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct XmlClass {
+    /// VM name of the class
+    name: String,
+    source_file_name: Option<String>,
+    methods: Vec<XmlMethod>,
+    counters: Counters,
+}
+
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct XmlMethod {
+    name: String,
+    desc: String,
+    /// first line of the method
+    line: Option<u32>,
+    counters: Counters,
+}
+
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct XmlSourceFile {
+    name: String,
+    lines: BTreeMap<u32, LineCoverage>,
+    counters: Counters,
+}
+
+impl XmlReport {
+    pub fn new(name: String, session_infos: Vec<SessionInfo>) -> Self {
+        Self {
+            name,
+            session_infos,
+            ..Default::default()
+        }
+    }
+
+    /// Builds a report from analyzed classes, the way JaCoCo's XML formatter does for a single
+    /// bundle: classes are grouped into packages, and the lines of all classes of the same
+    /// source file are merged into a source file element.
+    pub fn from_classes(
+        name: String,
+        session_infos: Vec<SessionInfo>,
+        classes: &[ClassCoverage],
+    ) -> Self {
+        let mut classes_by_package: BTreeMap<&str, Vec<&ClassCoverage>> = BTreeMap::new();
+        for class in classes {
+            classes_by_package
+                .entry(class.package_name())
+                .or_default()
+                .push(class);
+        }
+
+        let mut report = Self::new(name, session_infos);
+        for (package_name, mut classes) in classes_by_package {
+            classes.sort_by(|a, b| a.name().cmp(b.name()));
+            let package = XmlPackage::from_classes(package_name.to_string(), &classes);
+            add_counters(&mut report.counters, &package.counters);
+            report.packages.push(package);
+        }
+        report
+    }
+
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
+
+    pub fn session_infos(&self) -> &Vec<SessionInfo> {
+        &self.session_infos
+    }
+
+    pub fn session_infos_mut(&mut self) -> &mut Vec<SessionInfo> {
+        &mut self.session_infos
+    }
+
+    pub fn groups(&self) -> &Vec<XmlGroup> {
+        &self.groups
+    }
+
+    pub fn groups_mut(&mut self) -> &mut Vec<XmlGroup> {
+        &mut self.groups
+    }
+
+    pub fn packages(&self) -> &Vec<XmlPackage> {
+        &self.packages
+    }
+
+    pub fn packages_mut(&mut self) -> &mut Vec<XmlPackage> {
+        &mut self.packages
+    }
+
+    pub fn counters(&self) -> &Counters {
+        &self.counters
+    }
+
+    pub fn counters_mut(&mut self) -> &mut Counters {
+        &mut self.counters
+    }
+}
+
+impl XmlGroup {
+    pub fn new(name: String, groups: Vec<XmlGroup>, packages: Vec<XmlPackage>) -> Self {
+        let mut counters = Counters::default();
+        for group in &groups {
+            add_counters(&mut counters, &group.counters);
+        }
+        for package in &packages {
+            add_counters(&mut counters, &package.counters);
+        }
+        Self {
+            name,
+            groups,
+            packages,
+            counters,
+        }
+    }
+
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
+
+    pub fn groups(&self) -> &Vec<XmlGroup> {
+        &self.groups
+    }
+
+    pub fn groups_mut(&mut self) -> &mut Vec<XmlGroup> {
+        &mut self.groups
+    }
+
+    pub fn packages(&self) -> &Vec<XmlPackage> {
+        &self.packages
+    }
+
+    pub fn packages_mut(&mut self) -> &mut Vec<XmlPackage> {
+        &mut self.packages
+    }
+
+    pub fn counters(&self) -> &Counters {
+        &self.counters
+    }
+
+    pub fn counters_mut(&mut self) -> &mut Counters {
+        &mut self.counters
+    }
+}
+
+impl XmlPackage {
+    fn from_classes(name: String, classes: &[&ClassCoverage]) -> Self {
+        let mut package = Self {
+            name,
+            ..Default::default()
+        };
+
+        let mut classes_by_source_file: BTreeMap<&str, Vec<&ClassCoverage>> = BTreeMap::new();
+        for class in classes {
+            package.classes.push(XmlClass::from(*class));
+            match class.source_file() {
+                Some(source_file) => classes_by_source_file
+                    .entry(source_file)
+                    .or_default()
+                    .push(class),
+                // Classes with a source file are counted through their source file element
+                None => add_counters(&mut package.counters, class.counters()),
+            }
+        }
+
+        for (source_file_name, classes) in classes_by_source_file {
+            let mut source_file = XmlSourceFile {
+                name: source_file_name.to_string(),
+                ..Default::default()
+            };
+            for class in classes {
+                add_counters(&mut source_file.counters, class.counters());
+                for (nr, line) in class.lines() {
+                    source_file.lines.entry(*nr).or_default().increment(line);
+                }
+            }
+            *source_file.counters.get_mut(CounterEntity::Line) = line_counter(&source_file.lines);
+            add_counters(&mut package.counters, &source_file.counters);
+            package.source_files.push(source_file);
+        }
+
+        package
+    }
+
+    pub fn new(name: String, classes: Vec<XmlClass>, source_files: Vec<XmlSourceFile>) -> Self {
+        let mut counters = Counters::default();
+        for source_file in &source_files {
+            add_counters(&mut counters, &source_file.counters);
+        }
+        for class in classes.iter().filter(|c| c.source_file_name.is_none()) {
+            add_counters(&mut counters, &class.counters);
+        }
+        Self {
+            name,
+            classes,
+            source_files,
+            counters,
+        }
+    }
+
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
+
+    pub fn classes(&self) -> &Vec<XmlClass> {
+        &self.classes
+    }
+
+    pub fn classes_mut(&mut self) -> &mut Vec<XmlClass> {
+        &mut self.classes
+    }
+
+    pub fn source_files(&self) -> &Vec<XmlSourceFile> {
+        &self.source_files
+    }
+
+    pub fn source_files_mut(&mut self) -> &mut Vec<XmlSourceFile> {
+        &mut self.source_files
+    }
+
+    pub fn counters(&self) -> &Counters {
+        &self.counters
+    }
+
+    pub fn counters_mut(&mut self) -> &mut Counters {
+        &mut self.counters
+    }
+}
+
+impl From<&ClassCoverage> for XmlClass {
+    fn from(class: &ClassCoverage) -> Self {
+        let methods = class
+            .methods()
+            .iter()
+            .map(|method| XmlMethod {
+                name: method.name().clone(),
+                desc: method.desc().clone(),
+                line: method.first_line(),
+                counters: *method.counters(),
+            })
+            .collect();
+
+        Self {
+            name: class.name().clone(),
+            source_file_name: class.source_file().cloned(),
+            methods,
+            counters: *class.counters(),
+        }
+    }
+}
+
+impl XmlClass {
+    pub fn new(
+        name: String,
+        source_file_name: Option<String>,
+        methods: Vec<XmlMethod>,
+        counters: Counters,
+    ) -> Self {
+        Self {
+            name,
+            source_file_name,
+            methods,
+            counters,
+        }
+    }
+
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
+
+    pub fn source_file_name(&self) -> Option<&String> {
+        self.source_file_name.as_ref()
+    }
+
+    pub fn source_file_name_mut(&mut self) -> &mut Option<String> {
+        &mut self.source_file_name
+    }
+
+    pub fn methods(&self) -> &Vec<XmlMethod> {
+        &self.methods
+    }
+
+    pub fn methods_mut(&mut self) -> &mut Vec<XmlMethod> {
+        &mut self.methods
+    }
+
+    pub fn counters(&self) -> &Counters {
+        &self.counters
+    }
+
+    pub fn counters_mut(&mut self) -> &mut Counters {
+        &mut self.counters
+    }
+}
+
+impl XmlMethod {
+    pub fn new(name: String, desc: String, line: Option<u32>, counters: Counters) -> Self {
+        Self {
+            name,
+            desc,
+            line,
+            counters,
+        }
+    }
+
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
+
+    pub fn desc(&self) -> &String {
+        &self.desc
+    }
+
+    pub fn desc_mut(&mut self) -> &mut String {
+        &mut self.desc
+    }
+
+    pub fn line(&self) -> Option<u32> {
+        self.line
+    }
+
+    pub fn line_mut(&mut self) -> &mut Option<u32> {
+        &mut self.line
+    }
+
+    pub fn counters(&self) -> &Counters {
+        &self.counters
+    }
+
+    pub fn counters_mut(&mut self) -> &mut Counters {
+        &mut self.counters
+    }
+}
+
+impl XmlSourceFile {
+    pub fn new(name: String, lines: BTreeMap<u32, LineCoverage>, counters: Counters) -> Self {
+        Self {
+            name,
+            lines,
+            counters,
+        }
+    }
+
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
+
+    pub fn lines(&self) -> &BTreeMap<u32, LineCoverage> {
+        &self.lines
+    }
+
+    pub fn lines_mut(&mut self) -> &mut BTreeMap<u32, LineCoverage> {
+        &mut self.lines
+    }
+
+    pub fn counters(&self) -> &Counters {
+        &self.counters
+    }
+
+    pub fn counters_mut(&mut self) -> &mut Counters {
+        &mut self.counters
+    }
+}
+
+pub(super) fn add_counters(counters: &mut Counters, other: &Counters) {
+    for entity in CounterEntity::ALL {
+        counters.get_mut(entity).increment(other.get(entity));
+    }
+}
+
+impl XmlReport {
+    pub fn from_buf_read<R: BufRead>(r: &mut R) -> Result<Self> {
+        let mut reader = XmlElementReader::new(r);
+        while let Some(element) = reader.next_element()? {
+            if element.name == "report" {
+                return Self::read_report(&mut reader, &element);
+            }
+            reader.skip(&element)?;
+        }
+        Err(JacocoError::MissingXmlReportElement.into())
+    }
+
+    fn read_report<R: BufRead>(
+        reader: &mut XmlElementReader<R>,
+        element: &XmlElement,
+    ) -> Result<Self> {
+        let mut report = Self::new(required(element, "name")?.to_string(), vec![]);
+        Self::read_children(reader, element, &mut report.counters, |reader, child| {
+            match child.name.as_str() {
+                "sessioninfo" => report.session_infos.push(read_session_info(child)?),
+                "group" => report.groups.push(read_group(reader, child)?),
+                "package" => report.packages.push(read_package(reader, child)?),
+                _ => return Ok(false),
+            }
+            Ok(true)
+        })?;
+        Ok(report)
+    }
+
+    /// Reads the children of `element`. `f` handles element specific children and returns
+    /// whether it consumed the child, counters are handled here.
+    fn read_children<R, F>(
+        reader: &mut XmlElementReader<R>,
+        element: &XmlElement,
+        counters: &mut Counters,
+        mut f: F,
+    ) -> Result<()>
+    where
+        R: BufRead,
+        F: FnMut(&mut XmlElementReader<R>, &XmlElement) -> Result<bool>,
+    {
+        if element.is_empty {
+            return Ok(());
+        }
+        while let Some(child) = reader.next_element()? {
+            if child.name == "counter" {
+                let entity = CounterEntity::from_str(required(&child, "type")?)?;
+                *counters.get_mut(entity) = Counter::new(
+                    parse_required(&child, "missed")?,
+                    parse_required(&child, "covered")?,
+                );
+                reader.skip(&child)?;
+            } else if !f(reader, &child)? {
+                reader.skip(&child)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn read_session_info(element: &XmlElement) -> Result<SessionInfo> {
+    let id = required(element, "id")?.to_string();
+    let timestamp = |attribute: &str| -> Result<DateTime<Local>> {
+        let millis: i64 = parse_required(element, attribute)?;
+        Ok(DateTime::from_timestamp_millis(millis)
+            .ok_or(JacocoError::InvalidTimestamp(millis))?
+            .into())
+    };
+    Ok(SessionInfo::new(
+        id,
+        timestamp("start")?,
+        timestamp("dump")?,
+    ))
+}
+
+fn read_group<R: BufRead>(
+    reader: &mut XmlElementReader<R>,
+    element: &XmlElement,
+) -> Result<XmlGroup> {
+    let mut group = XmlGroup {
+        name: required(element, "name")?.to_string(),
+        ..Default::default()
+    };
+    XmlReport::read_children(reader, element, &mut group.counters, |reader, child| {
+        match child.name.as_str() {
+            "group" => group.groups.push(read_group(reader, child)?),
+            "package" => group.packages.push(read_package(reader, child)?),
+            _ => return Ok(false),
+        }
+        Ok(true)
+    })?;
+    Ok(group)
+}
+
+fn read_package<R: BufRead>(
+    reader: &mut XmlElementReader<R>,
+    element: &XmlElement,
+) -> Result<XmlPackage> {
+    let mut package = XmlPackage {
+        name: required(element, "name")?.to_string(),
+        ..Default::default()
+    };
+    XmlReport::read_children(reader, element, &mut package.counters, |reader, child| {
+        match child.name.as_str() {
+            "class" => package.classes.push(read_class(reader, child)?),
+            "sourcefile" => package.source_files.push(read_source_file(reader, child)?),
+            _ => return Ok(false),
+        }
+        Ok(true)
+    })?;
+    Ok(package)
+}
+
+fn read_class<R: BufRead>(
+    reader: &mut XmlElementReader<R>,
+    element: &XmlElement,
+) -> Result<XmlClass> {
+    let mut class = XmlClass {
+        name: required(element, "name")?.to_string(),
+        source_file_name: element.attribute("sourcefilename").map(str::to_string),
+        ..Default::default()
+    };
+    XmlReport::read_children(reader, element, &mut class.counters, |reader, child| {
+        if child.name != "method" {
+            return Ok(false);
+        }
+        let mut method = XmlMethod {
+            name: required(child, "name")?.to_string(),
+            desc: required(child, "desc")?.to_string(),
+            line: parse_optional(child, "line")?,
+            ..Default::default()
+        };
+        XmlReport::read_children(reader, child, &mut method.counters, |_, _| Ok(false))?;
+        class.methods.push(method);
+        Ok(true)
+    })?;
+    Ok(class)
+}
+
+fn read_source_file<R: BufRead>(
+    reader: &mut XmlElementReader<R>,
+    element: &XmlElement,
+) -> Result<XmlSourceFile> {
+    let mut source_file = XmlSourceFile {
+        name: required(element, "name")?.to_string(),
+        ..Default::default()
+    };
+    XmlReport::read_children(reader, element, &mut source_file.counters, |_, child| {
+        if child.name != "line" {
+            return Ok(false);
+        }
+        let counter = |missed: &str, covered: &str| -> Result<Counter> {
+            Ok(Counter::new(
+                parse_optional(child, missed)?.unwrap_or_default(),
+                parse_optional(child, covered)?.unwrap_or_default(),
+            ))
+        };
+        let line = LineCoverage::new(counter("mi", "ci")?, counter("mb", "cb")?);
+        source_file.lines.insert(parse_required(child, "nr")?, line);
+        Ok(false)
+    })?;
+    Ok(source_file)
+}
+
+fn required<'a>(element: &'a XmlElement, attribute: &str) -> Result<&'a str> {
+    element.attribute(attribute).ok_or_else(|| {
+        JacocoError::InvalidXmlAttribute {
+            element: element.name.clone(),
+            attribute: attribute.to_string(),
+        }
+        .into()
+    })
+}
+
+fn parse_required<T: FromStr>(element: &XmlElement, attribute: &str) -> Result<T> {
+    parse_optional(element, attribute)?.ok_or_else(|| {
+        JacocoError::InvalidXmlAttribute {
+            element: element.name.clone(),
+            attribute: attribute.to_string(),
+        }
+        .into()
+    })
+}
+
+fn parse_optional<T: FromStr>(element: &XmlElement, attribute: &str) -> Result<Option<T>> {
+    element
+        .attribute(attribute)
+        .map(|value| {
+            value.parse().map_err(|_| {
+                JacocoError::InvalidXmlAttribute {
+                    element: element.name.clone(),
+                    attribute: attribute.to_string(),
+                }
+                .into()
+            })
+        })
+        .transpose()
+}
+
+impl XmlReport {
+    /// Writes the report the same way JaCoCo's `XMLFormatter` does, i.e. without any
+    /// whitespace between elements and only with counters which have a non-zero total.
+    pub fn write_to<W: Write>(&self, w: &mut W) -> Result<()> {
+        write!(
+            w,
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><!DOCTYPE report PUBLIC "-//JACOCO//DTD Report 1.1//EN" "report.dtd">"#
+        )?;
+        write!(w, r#"<report name="{}">"#, escape(&self.name))?;
+        for session_info in &self.session_infos {
+            write!(
+                w,
+                r#"<sessioninfo id="{}" start="{}" dump="{}"/>"#,
+                escape(session_info.id()),
+                session_info.start().timestamp_millis(),
+                session_info.dump().timestamp_millis()
+            )?;
+        }
+        for group in &self.groups {
+            write_group(w, group)?;
+        }
+        for package in &self.packages {
+            write_package(w, package)?;
+        }
+        write_counters(w, &self.counters)?;
+        write!(w, "</report>")?;
+        Ok(())
+    }
+}
+
+fn write_group<W: Write>(w: &mut W, group: &XmlGroup) -> Result<()> {
+    write!(w, r#"<group name="{}">"#, escape(&group.name))?;
+    for child in &group.groups {
+        write_group(w, child)?;
+    }
+    for package in &group.packages {
+        write_package(w, package)?;
+    }
+    write_counters(w, &group.counters)?;
+    write!(w, "</group>")?;
+    Ok(())
+}
+
+fn write_package<W: Write>(w: &mut W, package: &XmlPackage) -> Result<()> {
+    write!(w, r#"<package name="{}">"#, escape(&package.name))?;
+    for class in &package.classes {
+        write!(w, r#"<class name="{}""#, escape(&class.name))?;
+        if let Some(source_file_name) = &class.source_file_name {
+            write!(w, r#" sourcefilename="{}""#, escape(source_file_name))?;
+        }
+        write!(w, ">")?;
+        for method in &class.methods {
+            write!(
+                w,
+                r#"<method name="{}" desc="{}""#,
+                escape(&method.name),
+                escape(&method.desc)
+            )?;
+            if let Some(line) = method.line {
+                write!(w, r#" line="{line}""#)?;
+            }
+            write!(w, ">")?;
+            write_counters(w, &method.counters)?;
+            write!(w, "</method>")?;
+        }
+        write_counters(w, &class.counters)?;
+        write!(w, "</class>")?;
+    }
+    for source_file in &package.source_files {
+        write!(w, r#"<sourcefile name="{}">"#, escape(&source_file.name))?;
+        for (nr, line) in &source_file.lines {
+            let (instructions, branches) = (line.instructions(), line.branches());
+            write!(
+                w,
+                r#"<line nr="{nr}" mi="{}" ci="{}" mb="{}" cb="{}"/>"#,
+                instructions.missed(),
+                instructions.covered(),
+                branches.missed(),
+                branches.covered()
+            )?;
+        }
+        write_counters(w, &source_file.counters)?;
+        write!(w, "</sourcefile>")?;
+    }
+    write_counters(w, &package.counters)?;
+    write!(w, "</package>")?;
+    Ok(())
+}
+
+fn write_counters<W: Write>(w: &mut W, counters: &Counters) -> Result<()> {
+    for entity in CounterEntity::ALL {
+        let counter = counters.get(entity);
+        if counter.total() > 0 {
+            write!(
+                w,
+                r#"<counter type="{}" missed="{}" covered="{}"/>"#,
+                entity.as_str(),
+                counter.missed(),
+                counter.covered()
+            )?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xml_report_round_trip_is_byte_exact() {
+        let bytes = include_bytes!("../../examples/files/jacoco.xml");
+        let report = XmlReport::from_buf_read(&mut bytes.as_slice()).unwrap();
+        let mut written = vec![];
+        report.write_to(&mut written).unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&written),
+            String::from_utf8_lossy(bytes)
+        );
+        assert_eq!(
+            XmlReport::from_buf_read(&mut written.as_slice()).unwrap(),
+            report
+        );
+    }
+}
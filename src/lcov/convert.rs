@@ -0,0 +1,76 @@
+//! Conversion between [`LcovReport`] and the format-neutral [`CoverageReport`].
+
+use super::{LcovBranch, LcovFunction, LcovLine, LcovRecord, LcovReport};
+use crate::coverage::{BranchCoverage, CoverageReport, FunctionCoverage};
+
+/// Records of different tests for the same source file are merged. The report is named after
+/// the first test with a name. Checksums, versions and whether a branch is taken by an exception
+/// are lost.
+impl From<&LcovReport> for CoverageReport {
+    fn from(report: &LcovReport) -> Self {
+        let name = report
+            .records
+            .iter()
+            .map(LcovRecord::test_name)
+            .find(|name| !name.is_empty())
+            .cloned();
+        let mut coverage = CoverageReport::new(name);
+        for record in &report.records {
+            let mut file_coverage = CoverageReport::new(None);
+            let file = file_coverage.file_mut(&record.source_file);
+            for (&nr, line) in &record.lines {
+                file.add_line_hits(nr, line.hits);
+            }
+            for branch in &record.branches {
+                file.branches_mut().push(BranchCoverage::new(
+                    branch.line,
+                    branch.block,
+                    branch.branch,
+                    branch.taken,
+                ));
+            }
+            for function in &record.functions {
+                file.functions_mut().push(FunctionCoverage::new(
+                    function.name.clone(),
+                    function.start_line,
+                    function.end_line,
+                    function.hits,
+                ));
+            }
+            coverage = coverage.merge(file_coverage);
+        }
+        coverage
+    }
+}
+
+impl LcovReport {
+    /// Builds a report with a record per file, all of them for a test named after the report.
+    /// Regions have no equivalent in LCOV and are dropped.
+    pub fn from_coverage(coverage: &CoverageReport) -> Self {
+        let test_name = coverage.name().cloned().unwrap_or_default();
+        let mut records = vec![];
+        for (path, file) in coverage.files() {
+            let mut record = LcovRecord::new(test_name.clone(), path.clone());
+            record.lines = file
+                .lines()
+                .iter()
+                .map(|(&nr, &hits)| (nr, LcovLine::new(hits, None)))
+                .collect();
+            record.branches = file
+                .branches()
+                .iter()
+                .map(|b| LcovBranch::new(b.line(), b.block(), b.branch(), false, b.taken()))
+                .collect();
+            record.functions = file
+                .functions()
+                .iter()
+                .map(|f| {
+                    LcovFunction::new(f.name().clone(), f.start_line(), f.end_line(), f.hits())
+                })
+                .collect();
+            record.sort();
+            records.push(record);
+        }
+        LcovReport::new(records)
+    }
+}
@@ -0,0 +1,17 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum LcovError {
+    #[error("Invalid line {line}: {reason}")]
+    InvalidLine { line: usize, reason: String },
+
+    #[error("Checksums of line {line} of {source_file} differ")]
+    ChecksumMismatch { source_file: String, line: u32 },
+
+    #[error("Versions {left} and {right} of {source_file} differ")]
+    VersionMismatch {
+        source_file: String,
+        left: String,
+        right: String,
+    },
+}
@@ -0,0 +1,173 @@
+//! Merging of tracefiles the way `lcov -a` does.
+
+use super::Result;
+use super::{LcovBranch, LcovError, LcovFunction, LcovLine, LcovRecord, LcovReport};
+
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+
+impl LcovReport {
+    /// Merges two reports like `lcov -a`: records of the same test and source file are combined,
+    /// adding up the hits of the same line, function and branch. A branch that was never
+    /// executed (`-`) in one report takes the count of the other. Records of different tests
+    /// are kept apart.
+    ///
+    /// # Errors
+    ///
+    /// Will return `LcovError::ChecksumMismatch` if a line has different checksums and
+    /// `LcovError::VersionMismatch` if a source file has different versions
+    pub fn try_merge(self, other: Self) -> Result<Self> {
+        Self::try_merge_all([self, other])
+    }
+
+    /// Merges any number of reports in a single pass, see [`LcovReport::try_merge`].
+    ///
+    /// # Errors
+    ///
+    /// See [`LcovReport::try_merge`]
+    pub fn try_merge_all<I: IntoIterator<Item = Self>>(reports: I) -> Result<Self> {
+        let mut merger = ReportMerger::default();
+        for report in reports {
+            for record in report.records {
+                merger.add_record(record)?;
+            }
+        }
+        Ok(merger.finish())
+    }
+}
+
+/// Records in the order their test and source file first appear.
+#[derive(Default)]
+pub(super) struct ReportMerger {
+    records: Vec<RecordMerger>,
+    index: HashMap<(String, String), usize>,
+}
+
+impl ReportMerger {
+    /// The record of a test and source file, added if it doesn't exist yet.
+    pub(super) fn record(&mut self, test_name: &str, source_file: &str) -> &mut RecordMerger {
+        let key = (test_name.to_string(), source_file.to_string());
+        let i = *self.index.entry(key).or_insert_with(|| {
+            self.records.push(RecordMerger::new(
+                test_name.to_string(),
+                source_file.to_string(),
+            ));
+            self.records.len() - 1
+        });
+        &mut self.records[i]
+    }
+
+    fn add_record(&mut self, record: LcovRecord) -> Result<()> {
+        let merger = self.record(&record.test_name, &record.source_file);
+        if let Some(version) = record.version {
+            merger.set_version(version)?;
+        }
+        for function in record.functions {
+            merger.add_function(function);
+        }
+        for branch in record.branches {
+            merger.add_branch(branch);
+        }
+        for (nr, line) in record.lines {
+            merger.add_line(nr, line)?;
+        }
+        Ok(())
+    }
+
+    pub(super) fn finish(self) -> LcovReport {
+        LcovReport::new(self.records.into_iter().map(RecordMerger::finish).collect())
+    }
+}
+
+/// Collects the data of a record, adding up duplicates.
+pub(super) struct RecordMerger {
+    record: LcovRecord,
+    functions: HashMap<String, LcovFunction>,
+    branches: HashMap<(u32, u32, u32, bool), LcovBranch>,
+    lines: BTreeMap<u32, LcovLine>,
+}
+
+impl RecordMerger {
+    fn new(test_name: String, source_file: String) -> Self {
+        Self {
+            record: LcovRecord::new(test_name, source_file),
+            functions: HashMap::new(),
+            branches: HashMap::new(),
+            lines: BTreeMap::new(),
+        }
+    }
+
+    pub(super) fn set_version(&mut self, version: String) -> Result<()> {
+        match &self.record.version {
+            Some(existing) if *existing != version => Err(LcovError::VersionMismatch {
+                source_file: self.record.source_file.clone(),
+                left: existing.clone(),
+                right: version,
+            }
+            .into()),
+            _ => {
+                self.record.version = Some(version);
+                Ok(())
+            }
+        }
+    }
+
+    /// Functions are identified by name, the first known position is kept.
+    pub(super) fn add_function(&mut self, function: LcovFunction) {
+        match self.functions.entry(function.name.clone()) {
+            Entry::Occupied(mut occ) => {
+                let existing = occ.get_mut();
+                existing.hits = existing.hits.saturating_add(function.hits);
+                existing.start_line = existing.start_line.or(function.start_line);
+                existing.end_line = existing.end_line.or(function.end_line);
+            }
+            Entry::Vacant(vac) => {
+                vac.insert(function);
+            }
+        }
+    }
+
+    pub(super) fn add_branch(&mut self, branch: LcovBranch) {
+        match self.branches.entry(branch.key()) {
+            Entry::Occupied(mut occ) => {
+                let existing = occ.get_mut();
+                existing.taken = match (existing.taken, branch.taken) {
+                    (Some(a), Some(b)) => Some(a.saturating_add(b)),
+                    (a, b) => a.or(b),
+                };
+            }
+            Entry::Vacant(vac) => {
+                vac.insert(branch);
+            }
+        }
+    }
+
+    pub(super) fn add_line(&mut self, nr: u32, line: LcovLine) -> Result<()> {
+        let Some(existing) = self.lines.get_mut(&nr) else {
+            self.lines.insert(nr, line);
+            return Ok(());
+        };
+        match (&existing.checksum, line.checksum) {
+            (Some(a), Some(b)) if *a != b => {
+                return Err(LcovError::ChecksumMismatch {
+                    source_file: self.record.source_file.clone(),
+                    line: nr,
+                }
+                .into());
+            }
+            (None, checksum) => existing.checksum = checksum,
+            _ => {}
+        }
+        existing.hits = existing.hits.saturating_add(line.hits);
+        Ok(())
+    }
+
+    fn finish(self) -> LcovRecord {
+        let mut record = self.record;
+        record.functions = self.functions.into_values().collect();
+        record.branches = self.branches.into_values().collect();
+        record.lines = self.lines;
+        record.sort();
+        record
+    }
+}
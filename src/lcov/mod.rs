@@ -0,0 +1,306 @@
+//! LCOV tracefiles (`.info`) as written by `lcov`/`geninfo`, `cargo llvm-cov --lcov`, Istanbul
+//! and most other coverage tools.
+
+use std::collections::BTreeMap;
+
+pub(super) use super::Result;
+pub mod error;
+pub use error::LcovError;
+
+pub mod convert;
+pub mod merge;
+pub mod reader;
+pub mod writer;
+
+#[cfg(feature = "serialization")]
+use serde::{Deserialize, Serialize};
+
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct LcovReport {
+    records: Vec<LcovRecord>,
+}
+
+/// Coverage of a source file in a test, from `SF` to `end_of_record`.
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct LcovRecord {
+    /// `TN`, empty if the tracefile has none
+    test_name: String,
+    /// `SF`
+    source_file: String,
+    /// `VER`, version of the source file, e.g. a git revision
+    version: Option<String>,
+    /// Sorted by start line and name
+    functions: Vec<LcovFunction>,
+    /// Sorted by line, block and branch
+    branches: Vec<LcovBranch>,
+    lines: BTreeMap<u32, LcovLine>,
+}
+
+/// `FN` and `FNDA` records of a function.
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct LcovFunction {
+    name: String,
+    /// `None` if there is an `FNDA` but no `FN` record
+    start_line: Option<u32>,
+    end_line: Option<u32>,
+    hits: u64,
+}
+
+/// `BRDA` record.
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct LcovBranch {
+    line: u32,
+    block: u32,
+    branch: u32,
+    /// Branch taken by an exception, written with an `e` before the block
+    exception: bool,
+    /// `None` for `-`, the code containing the branch was never executed
+    taken: Option<u64>,
+}
+
+/// `DA` record.
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct LcovLine {
+    hits: u64,
+    /// Checksum of the source line, base64 encoded MD5 if written by `geninfo --checksum`
+    checksum: Option<String>,
+}
+
+impl LcovReport {
+    pub fn new(records: Vec<LcovRecord>) -> Self {
+        Self { records }
+    }
+
+    pub fn records(&self) -> &Vec<LcovRecord> {
+        &self.records
+    }
+
+    pub fn records_mut(&mut self) -> &mut Vec<LcovRecord> {
+        &mut self.records
+    }
+}
+
+impl LcovRecord {
+    pub fn new(test_name: String, source_file: String) -> Self {
+        Self {
+            test_name,
+            source_file,
+            ..Default::default()
+        }
+    }
+
+    pub fn test_name(&self) -> &String {
+        &self.test_name
+    }
+
+    pub fn test_name_mut(&mut self) -> &mut String {
+        &mut self.test_name
+    }
+
+    pub fn source_file(&self) -> &String {
+        &self.source_file
+    }
+
+    pub fn source_file_mut(&mut self) -> &mut String {
+        &mut self.source_file
+    }
+
+    pub fn version(&self) -> Option<&String> {
+        self.version.as_ref()
+    }
+
+    pub fn version_mut(&mut self) -> &mut Option<String> {
+        &mut self.version
+    }
+
+    pub fn functions(&self) -> &Vec<LcovFunction> {
+        &self.functions
+    }
+
+    pub fn functions_mut(&mut self) -> &mut Vec<LcovFunction> {
+        &mut self.functions
+    }
+
+    pub fn branches(&self) -> &Vec<LcovBranch> {
+        &self.branches
+    }
+
+    pub fn branches_mut(&mut self) -> &mut Vec<LcovBranch> {
+        &mut self.branches
+    }
+
+    pub fn lines(&self) -> &BTreeMap<u32, LcovLine> {
+        &self.lines
+    }
+
+    pub fn lines_mut(&mut self) -> &mut BTreeMap<u32, LcovLine> {
+        &mut self.lines
+    }
+
+    /// `FNF`
+    pub fn functions_found(&self) -> usize {
+        self.functions.len()
+    }
+
+    /// `FNH`
+    pub fn functions_hit(&self) -> usize {
+        self.functions.iter().filter(|f| f.hits > 0).count()
+    }
+
+    /// `BRF`
+    pub fn branches_found(&self) -> usize {
+        self.branches.len()
+    }
+
+    /// `BRH`
+    pub fn branches_hit(&self) -> usize {
+        self.branches
+            .iter()
+            .filter(|b| b.taken.is_some_and(|taken| taken > 0))
+            .count()
+    }
+
+    /// `LF`
+    pub fn lines_found(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// `LH`
+    pub fn lines_hit(&self) -> usize {
+        self.lines.values().filter(|l| l.hits > 0).count()
+    }
+
+    /// Sorts functions and branches in the order the reader returns them.
+    fn sort(&mut self) {
+        self.functions
+            .sort_by(|a, b| (a.start_line, &a.name).cmp(&(b.start_line, &b.name)));
+        self.branches.sort_by_key(LcovBranch::key);
+    }
+}
+
+impl LcovFunction {
+    pub fn new(name: String, start_line: Option<u32>, end_line: Option<u32>, hits: u64) -> Self {
+        Self {
+            name,
+            start_line,
+            end_line,
+            hits,
+        }
+    }
+
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
+
+    pub fn start_line(&self) -> Option<u32> {
+        self.start_line
+    }
+
+    pub fn start_line_mut(&mut self) -> &mut Option<u32> {
+        &mut self.start_line
+    }
+
+    pub fn end_line(&self) -> Option<u32> {
+        self.end_line
+    }
+
+    pub fn end_line_mut(&mut self) -> &mut Option<u32> {
+        &mut self.end_line
+    }
+
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    pub fn hits_mut(&mut self) -> &mut u64 {
+        &mut self.hits
+    }
+}
+
+impl LcovBranch {
+    pub fn new(line: u32, block: u32, branch: u32, exception: bool, taken: Option<u64>) -> Self {
+        Self {
+            line,
+            block,
+            branch,
+            exception,
+            taken,
+        }
+    }
+
+    pub fn line(&self) -> u32 {
+        self.line
+    }
+
+    pub fn line_mut(&mut self) -> &mut u32 {
+        &mut self.line
+    }
+
+    pub fn block(&self) -> u32 {
+        self.block
+    }
+
+    pub fn block_mut(&mut self) -> &mut u32 {
+        &mut self.block
+    }
+
+    pub fn branch(&self) -> u32 {
+        self.branch
+    }
+
+    pub fn branch_mut(&mut self) -> &mut u32 {
+        &mut self.branch
+    }
+
+    pub fn exception(&self) -> bool {
+        self.exception
+    }
+
+    pub fn exception_mut(&mut self) -> &mut bool {
+        &mut self.exception
+    }
+
+    pub fn taken(&self) -> Option<u64> {
+        self.taken
+    }
+
+    pub fn taken_mut(&mut self) -> &mut Option<u64> {
+        &mut self.taken
+    }
+
+    /// Position identifying the branch, in sort order.
+    fn key(&self) -> (u32, u32, u32, bool) {
+        (self.line, self.block, self.branch, self.exception)
+    }
+}
+
+impl LcovLine {
+    pub fn new(hits: u64, checksum: Option<String>) -> Self {
+        Self { hits, checksum }
+    }
+
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    pub fn hits_mut(&mut self) -> &mut u64 {
+        &mut self.hits
+    }
+
+    pub fn checksum(&self) -> Option<&String> {
+        self.checksum.as_ref()
+    }
+
+    pub fn checksum_mut(&mut self) -> &mut Option<String> {
+        &mut self.checksum
+    }
+}
@@ -0,0 +1,169 @@
+use super::Result;
+use super::merge::{RecordMerger, ReportMerger};
+use super::{LcovBranch, LcovError, LcovFunction, LcovLine, LcovReport};
+
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::str::FromStr;
+
+impl LcovReport {
+    /// Reads a tracefile. Summary records (`FNF`, `FNH`, `BRF`, `BRH`, `LF`, `LH`) are ignored
+    /// and computed from the data when writing, so they may be missing or wrong, as may the
+    /// final `end_of_record`. Line checksums, `-` branch counts, function end lines and the
+    /// `FNL`/`FNA` function records of lcov 2.2 are supported, unknown records are skipped.
+    /// Repeated records of the same test and source file are merged like in
+    /// [`LcovReport::try_merge`].
+    ///
+    /// # Errors
+    ///
+    /// Will return `LcovError::InvalidLine` with the 1-based line number and the reason for the
+    /// first invalid line, `LcovError::ChecksumMismatch` or `LcovError::VersionMismatch` if
+    /// repeated records don't match and the error of `r` if reading fails
+    pub fn from_buf_read<R: BufRead>(r: &mut R) -> Result<Self> {
+        let mut parser = Parser::default();
+        let mut buf = vec![];
+        let mut line_number = 0;
+        loop {
+            buf.clear();
+            if r.read_until(b'\n', &mut buf)? == 0 {
+                break;
+            }
+            line_number += 1;
+
+            let invalid_line = |reason: String| LcovError::InvalidLine {
+                line: line_number,
+                reason,
+            };
+            let line =
+                std::str::from_utf8(&buf).map_err(|_| invalid_line("invalid UTF-8".to_string()))?;
+            let line = line.trim_end_matches('\n').trim_end_matches('\r');
+            if line.trim().is_empty() {
+                continue;
+            }
+            match parser.parse_line(line) {
+                Ok(result) => result?,
+                Err(reason) => return Err(invalid_line(reason).into()),
+            }
+        }
+        Ok(parser.merger.finish())
+    }
+}
+
+#[derive(Default)]
+struct Parser {
+    merger: ReportMerger,
+    /// Applies to all following records
+    test_name: String,
+    /// Test name and source file of the current record
+    current: Option<(String, String)>,
+    /// Positions of the `FNL` records of the current record by index
+    function_lines: HashMap<u32, (u32, Option<u32>)>,
+}
+
+impl Parser {
+    /// Fails with the reason if the line is invalid, the inner result is the error of merging
+    /// the line into its record.
+    fn parse_line(&mut self, line: &str) -> std::result::Result<Result<()>, String> {
+        let (tag, value) = line.split_once(':').unwrap_or((line, ""));
+        match tag {
+            "TN" => self.test_name = value.to_string(),
+            "SF" => {
+                self.current = Some((self.test_name.clone(), value.to_string()));
+                self.function_lines.clear();
+                // Keeps records without data
+                self.record(tag)?;
+            }
+            "end_of_record" => self.current = None,
+            "VER" => return Ok(self.record(tag)?.set_version(value.to_string())),
+            "FN" => {
+                let (start, rest) = split_field(value, "start line")?;
+                let (end, name) = match rest.split_once(',') {
+                    Some((end, name)) if end.parse::<u32>().is_ok() => (Some(parse(end)?), name),
+                    _ => (None, rest),
+                };
+                let function = LcovFunction::new(name.to_string(), Some(parse(start)?), end, 0);
+                self.record(tag)?.add_function(function);
+            }
+            "FNDA" => {
+                let (hits, name) = split_field(value, "hit count")?;
+                let function = LcovFunction::new(name.to_string(), None, None, parse(hits)?);
+                self.record(tag)?.add_function(function);
+            }
+            "FNL" => {
+                let (index, lines) = split_field(value, "index")?;
+                let position = match lines.split_once(',') {
+                    Some((start, end)) => (parse(start)?, Some(parse(end)?)),
+                    None => (parse(lines)?, None),
+                };
+                self.record(tag)?;
+                self.function_lines.insert(parse(index)?, position);
+            }
+            "FNA" => {
+                let (index, rest) = split_field(value, "index")?;
+                let (hits, name) = split_field(rest, "hit count")?;
+                let index: u32 = parse(index)?;
+                let &(start, end) = self
+                    .function_lines
+                    .get(&index)
+                    .ok_or_else(|| format!("no FNL record for function index {index}"))?;
+                let function = LcovFunction::new(name.to_string(), Some(start), end, parse(hits)?);
+                self.record(tag)?.add_function(function);
+            }
+            "BRDA" => {
+                let fields: Vec<&str> = value.split(',').collect();
+                let [line, block, branch, taken] = fields[..] else {
+                    return Err(format!("expected 4 fields, got {}", fields.len()));
+                };
+                let (exception, block) = match block.strip_prefix('e') {
+                    Some(block) => (true, block),
+                    None => (false, block),
+                };
+                let taken = match taken {
+                    "-" => None,
+                    taken => Some(parse(taken)?),
+                };
+                let branch = LcovBranch::new(
+                    parse(line)?,
+                    parse(block)?,
+                    parse(branch)?,
+                    exception,
+                    taken,
+                );
+                self.record(tag)?.add_branch(branch);
+            }
+            "DA" => {
+                let mut fields = value.splitn(3, ',');
+                let (Some(nr), Some(hits)) = (fields.next(), fields.next()) else {
+                    return Err("expected line number and hit count".to_string());
+                };
+                let line = LcovLine::new(parse(hits)?, fields.next().map(str::to_string));
+                return Ok(self.record(tag)?.add_line(parse(nr)?, line));
+            }
+            // Summaries are computed from the data, records of newer versions are skipped
+            _ => {}
+        }
+        Ok(Ok(()))
+    }
+
+    /// The current record, `tag` is a record that has to be part of one.
+    fn record(&mut self, tag: &str) -> std::result::Result<&mut RecordMerger, String> {
+        let (test_name, source_file) = self
+            .current
+            .as_ref()
+            .ok_or_else(|| format!("{tag} outside of a source file record"))?;
+        Ok(self.merger.record(test_name, source_file))
+    }
+}
+
+fn split_field<'a>(value: &'a str, field: &str) -> std::result::Result<(&'a str, &'a str), String> {
+    value
+        .split_once(',')
+        .ok_or_else(|| format!("expected {field} followed by ','"))
+}
+
+fn parse<T: FromStr>(field: &str) -> std::result::Result<T, String> {
+    field
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid number {field:?}"))
+}
@@ -0,0 +1,120 @@
+use super::Result;
+use super::{LcovFunction, LcovRecord, LcovReport};
+
+use std::io::Write;
+
+impl LcovReport {
+    /// Writes the report in the record order of `lcov`, with summaries computed from the data.
+    /// Functions are written as `FN`/`FNDA` records, which all versions of `lcov` read. Only
+    /// functions without end line whose name starts with `<digits>,` are written as `FNL`/`FNA`
+    /// records, as `FN:<start>,<digits>,<rest>` would be read back with an end line.
+    ///
+    /// # Errors
+    ///
+    /// Will return the error of `w` if writing fails
+    pub fn write_to<W: Write>(&self, w: &mut W) -> Result<()> {
+        for record in &self.records {
+            write_record(w, record)?;
+        }
+        Ok(())
+    }
+}
+
+fn write_record<W: Write>(w: &mut W, record: &LcovRecord) -> Result<()> {
+    writeln!(w, "TN:{}", record.test_name)?;
+    writeln!(w, "SF:{}", record.source_file)?;
+    if let Some(version) = &record.version {
+        writeln!(w, "VER:{version}")?;
+    }
+
+    let (ambiguous, functions): (Vec<_>, Vec<_>) = record
+        .functions
+        .iter()
+        .partition(|function| has_ambiguous_name(function));
+    for function in &functions {
+        match (function.start_line, function.end_line) {
+            (Some(start), Some(end)) => writeln!(w, "FN:{start},{end},{}", function.name)?,
+            (Some(start), None) => writeln!(w, "FN:{start},{}", function.name)?,
+            (None, _) => {}
+        }
+    }
+    for function in &functions {
+        writeln!(w, "FNDA:{},{}", function.hits, function.name)?;
+    }
+    for (index, function) in ambiguous.iter().enumerate() {
+        let start = function.start_line.unwrap_or_default();
+        writeln!(w, "FNL:{index},{start}")?;
+        writeln!(w, "FNA:{index},{},{}", function.hits, function.name)?;
+    }
+    writeln!(w, "FNF:{}", record.functions_found())?;
+    writeln!(w, "FNH:{}", record.functions_hit())?;
+
+    for branch in &record.branches {
+        let exception = if branch.exception { "e" } else { "" };
+        write!(
+            w,
+            "BRDA:{},{exception}{},{},",
+            branch.line, branch.block, branch.branch
+        )?;
+        match branch.taken {
+            Some(taken) => writeln!(w, "{taken}")?,
+            None => writeln!(w, "-")?,
+        }
+    }
+    writeln!(w, "BRF:{}", record.branches_found())?;
+    writeln!(w, "BRH:{}", record.branches_hit())?;
+
+    for (nr, line) in &record.lines {
+        match &line.checksum {
+            Some(checksum) => writeln!(w, "DA:{nr},{},{checksum}", line.hits)?,
+            None => writeln!(w, "DA:{nr},{}", line.hits)?,
+        }
+    }
+    writeln!(w, "LF:{}", record.lines_found())?;
+    writeln!(w, "LH:{}", record.lines_hit())?;
+    writeln!(w, "end_of_record")?;
+    Ok(())
+}
+
+/// Whether `FN:<start>,<name>` would be read as start line, end line and the rest of the name.
+fn has_ambiguous_name(function: &LcovFunction) -> bool {
+    function.start_line.is_some()
+        && function.end_line.is_none()
+        && function
+            .name
+            .split_once(',')
+            .is_some_and(|(prefix, _)| prefix.parse::<u32>().is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracefile_round_trip_is_byte_exact() {
+        let bytes = include_bytes!("../../examples/files/lcov.info");
+        let report = LcovReport::from_buf_read(&mut bytes.as_slice()).unwrap();
+        let mut written = vec![];
+        report.write_to(&mut written).unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&written),
+            String::from_utf8_lossy(bytes)
+        );
+        assert_eq!(
+            LcovReport::from_buf_read(&mut written.as_slice()).unwrap(),
+            report
+        );
+    }
+
+    #[test]
+    fn function_with_ambiguous_name_round_trip() {
+        let bytes = b"TN:\nSF:a.c\nFNL:0,3\nFNA:0,1,12,main\nFNF:1\nFNH:1\nBRF:0\nBRH:0\nLF:0\nLH:0\nend_of_record\n";
+        let report = LcovReport::from_buf_read(&mut bytes.as_slice()).unwrap();
+        let mut written = vec![];
+        report.write_to(&mut written).unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&written),
+            String::from_utf8_lossy(bytes)
+        );
+    }
+}
@@ -1,7 +1,14 @@
 #[forbid(unsafe_code)]
 pub mod result;
 
+pub mod cobertura;
+pub mod coverage;
+pub mod format;
 pub mod go;
 pub mod jacoco;
+pub mod lcov;
+pub mod report;
+
+mod xml;
 
 pub use result::*;
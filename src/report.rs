@@ -0,0 +1,215 @@
+//! Traits implemented by the reports of every format, so generic code can read, write and merge
+//! any of them.
+
+use crate::Result;
+use crate::cobertura::CoberturaReport;
+use crate::coverage::CoverageReport;
+use crate::go::GoReport;
+use crate::go::covdata::{CounterFile, CovData, MetaFile};
+use crate::jacoco::{CsvReport, JacocoReport, XmlReport};
+use crate::lcov::LcovReport;
+
+use std::io::{BufRead, Write};
+
+/// A report that can be read from its serialized format.
+pub trait CoverageReader: Sized {
+    /// Reads a complete report.
+    ///
+    /// # Errors
+    ///
+    /// Will return the format's error if the input is invalid and the error of `r` if reading
+    /// fails
+    fn from_buf_read<R: BufRead>(r: &mut R) -> Result<Self>;
+}
+
+/// A report that can be written in its serialized format.
+pub trait CoverageWriter {
+    /// Writes the report in its format, so it can be read again by [`CoverageReader`].
+    ///
+    /// # Errors
+    ///
+    /// Will return the error of `w` if writing fails
+    fn write_to<W: Write>(&self, w: &mut W) -> Result<()>;
+}
+
+/// Reports of several runs that can be combined into one.
+pub trait Merge: Sized {
+    /// Merges the coverage of two runs.
+    ///
+    /// # Errors
+    ///
+    /// Will return the format's error if the reports are incompatible
+    fn try_merge(self, other: Self) -> Result<Self>;
+
+    /// Merges any number of reports, `None` if there are none.
+    ///
+    /// # Errors
+    ///
+    /// Will return the format's error if the reports are incompatible
+    fn try_merge_all<I: IntoIterator<Item = Self>>(reports: I) -> Result<Option<Self>> {
+        let mut reports = reports.into_iter();
+        let Some(first) = reports.next() else {
+            return Ok(None);
+        };
+        reports.try_fold(first, Self::try_merge).map(Some)
+    }
+}
+
+impl CoverageReader for GoReport {
+    fn from_buf_read<R: BufRead>(r: &mut R) -> Result<Self> {
+        GoReport::from_buf_read(r)
+    }
+}
+
+impl CoverageWriter for GoReport {
+    fn write_to<W: Write>(&self, w: &mut W) -> Result<()> {
+        GoReport::write_to(self, w)
+    }
+}
+
+/// Modes have to match, see [`GoReport::try_merge_with`] for merging different modes.
+impl Merge for GoReport {
+    fn try_merge(self, other: Self) -> Result<Self> {
+        GoReport::try_merge(self, other)
+    }
+
+    fn try_merge_all<I: IntoIterator<Item = Self>>(reports: I) -> Result<Option<Self>> {
+        let mut reports = reports.into_iter().peekable();
+        if reports.peek().is_none() {
+            return Ok(None);
+        }
+        GoReport::try_merge_all(reports, Default::default()).map(Some)
+    }
+}
+
+impl CoverageReader for MetaFile {
+    fn from_buf_read<R: BufRead>(r: &mut R) -> Result<Self> {
+        MetaFile::from_read(r)
+    }
+}
+
+impl CoverageWriter for MetaFile {
+    fn write_to<W: Write>(&self, w: &mut W) -> Result<()> {
+        MetaFile::write_to(self, w)
+    }
+}
+
+impl CoverageReader for CounterFile {
+    fn from_buf_read<R: BufRead>(r: &mut R) -> Result<Self> {
+        CounterFile::from_read(r)
+    }
+}
+
+impl CoverageWriter for CounterFile {
+    fn write_to<W: Write>(&self, w: &mut W) -> Result<()> {
+        CounterFile::write_to(self, w)
+    }
+}
+
+impl Merge for CovData {
+    fn try_merge(self, other: Self) -> Result<Self> {
+        CovData::try_merge(self, other)
+    }
+
+    fn try_merge_all<I: IntoIterator<Item = Self>>(cov_datas: I) -> Result<Option<Self>> {
+        let mut cov_datas = cov_datas.into_iter().peekable();
+        if cov_datas.peek().is_none() {
+            return Ok(None);
+        }
+        CovData::try_merge_all(cov_datas).map(Some)
+    }
+}
+
+impl CoverageReader for JacocoReport {
+    fn from_buf_read<R: BufRead>(r: &mut R) -> Result<Self> {
+        JacocoReport::from_read(r)
+    }
+}
+
+impl CoverageWriter for JacocoReport {
+    fn write_to<W: Write>(&self, w: &mut W) -> Result<()> {
+        JacocoReport::write_to(self, w)
+    }
+}
+
+impl Merge for JacocoReport {
+    fn try_merge(self, other: Self) -> Result<Self> {
+        JacocoReport::try_merge(self, other)
+    }
+
+    fn try_merge_all<I: IntoIterator<Item = Self>>(reports: I) -> Result<Option<Self>> {
+        let mut reports = reports.into_iter().peekable();
+        if reports.peek().is_none() {
+            return Ok(None);
+        }
+        JacocoReport::try_merge_all(reports).map(Some)
+    }
+}
+
+impl CoverageReader for XmlReport {
+    fn from_buf_read<R: BufRead>(r: &mut R) -> Result<Self> {
+        XmlReport::from_buf_read(r)
+    }
+}
+
+impl CoverageWriter for XmlReport {
+    fn write_to<W: Write>(&self, w: &mut W) -> Result<()> {
+        XmlReport::write_to(self, w)
+    }
+}
+
+impl CoverageReader for CsvReport {
+    fn from_buf_read<R: BufRead>(r: &mut R) -> Result<Self> {
+        CsvReport::from_buf_read(r)
+    }
+}
+
+impl CoverageWriter for CsvReport {
+    fn write_to<W: Write>(&self, w: &mut W) -> Result<()> {
+        CsvReport::write_to(self, w)
+    }
+}
+
+impl CoverageReader for LcovReport {
+    fn from_buf_read<R: BufRead>(r: &mut R) -> Result<Self> {
+        LcovReport::from_buf_read(r)
+    }
+}
+
+impl CoverageWriter for LcovReport {
+    fn write_to<W: Write>(&self, w: &mut W) -> Result<()> {
+        LcovReport::write_to(self, w)
+    }
+}
+
+impl Merge for LcovReport {
+    fn try_merge(self, other: Self) -> Result<Self> {
+        LcovReport::try_merge(self, other)
+    }
+
+    fn try_merge_all<I: IntoIterator<Item = Self>>(reports: I) -> Result<Option<Self>> {
+        let mut reports = reports.into_iter().peekable();
+        if reports.peek().is_none() {
+            return Ok(None);
+        }
+        LcovReport::try_merge_all(reports).map(Some)
+    }
+}
+
+impl CoverageReader for CoberturaReport {
+    fn from_buf_read<R: BufRead>(r: &mut R) -> Result<Self> {
+        CoberturaReport::from_buf_read(r)
+    }
+}
+
+impl CoverageWriter for CoberturaReport {
+    fn write_to<W: Write>(&self, w: &mut W) -> Result<()> {
+        CoberturaReport::write_to(self, w)
+    }
+}
+
+impl Merge for CoverageReport {
+    fn try_merge(self, other: Self) -> Result<Self> {
+        Ok(self.merge(other))
+    }
+}
@@ -1,5 +1,8 @@
+use super::cobertura::CoberturaError;
+use super::format::CoverageFormat;
 use super::go::GoCoverageError;
 use crate::jacoco::JacocoError;
+use crate::lcov::LcovError;
 
 pub type Result<T> = core::result::Result<T, Error>;
 
@@ -11,9 +14,27 @@ pub enum Error {
     #[error(transparent)]
     Go(GoCoverageError),
 
+    #[error(transparent)]
+    Lcov(LcovError),
+
+    #[error(transparent)]
+    Cobertura(CoberturaError),
+
+    #[error("Unknown coverage format")]
+    UnknownFormat,
+
+    #[error("A {0} can't be converted into a coverage report on its own")]
+    UnsupportedConversion(CoverageFormat),
+
     #[error(transparent)]
     Io(#[from] std::io::Error),
 
+    #[error(transparent)]
+    Xml(#[from] quick_xml::Error),
+
+    #[error(transparent)]
+    Zip(#[from] zip::result::ZipError),
+
     #[error(transparent)]
     FromUtf8(#[from] std::string::FromUtf8Error),
 
@@ -35,3 +56,15 @@ impl From<GoCoverageError> for Error {
         Self::Go(value)
     }
 }
+
+impl From<LcovError> for Error {
+    fn from(value: LcovError) -> Self {
+        Self::Lcov(value)
+    }
+}
+
+impl From<CoberturaError> for Error {
+    fn from(value: CoberturaError) -> Self {
+        Self::Cobertura(value)
+    }
+}
@@ -0,0 +1,168 @@
+//! Minimal event based XML helpers shared by the XML based formats.
+
+use super::Result;
+
+use quick_xml::XmlVersion;
+use quick_xml::escape::{EscapeError, resolve_xml_entity};
+use quick_xml::events::{BytesStart, Event};
+use std::borrow::Cow;
+use std::io::{BufRead, ErrorKind};
+
+/// A start or empty element tag with its attributes.
+pub(crate) struct XmlElement {
+    pub(crate) name: String,
+    attributes: Vec<(String, String)>,
+    /// `true` for `<element/>`, i.e. no children and no end tag follow
+    pub(crate) is_empty: bool,
+}
+
+impl XmlElement {
+    pub(crate) fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn from_bytes_start(start: &BytesStart, is_empty: bool) -> Result<Self> {
+        let name = start.local_name().as_ref().to_string();
+        let mut attributes = vec![];
+        for attribute in start.attributes() {
+            let attribute = attribute.map_err(quick_xml::Error::from)?;
+            let key = attribute.key.local_name().as_ref().to_string();
+            let value = attribute.normalized_value(XmlVersion::Implicit1_0)?;
+            attributes.push((key, value.into_owned()));
+        }
+        Ok(Self {
+            name,
+            attributes,
+            is_empty,
+        })
+    }
+}
+
+/// Pull reader which only reports elements, skipping declarations, comments and whitespace.
+pub(crate) struct XmlElementReader<R: BufRead> {
+    reader: quick_xml::Reader<R>,
+    buf: Vec<u8>,
+    depth: usize,
+}
+
+impl<R: BufRead> XmlElementReader<R> {
+    pub(crate) fn new(r: R) -> Self {
+        let mut reader = quick_xml::Reader::from_reader(r);
+        reader.config_mut().trim_text(true);
+        Self {
+            reader,
+            buf: vec![],
+            depth: 0,
+        }
+    }
+
+    /// Returns the next child element of the current element, or `None` once the end tag of
+    /// the current element (or the end of the document on the top level) is reached.
+    pub(crate) fn next_element(&mut self) -> Result<Option<XmlElement>> {
+        loop {
+            self.buf.clear();
+            match self.reader.read_event_into(&mut self.buf)? {
+                Event::Start(start) => {
+                    self.depth += 1;
+                    return Ok(Some(XmlElement::from_bytes_start(&start, false)?));
+                }
+                Event::Empty(start) => {
+                    return Ok(Some(XmlElement::from_bytes_start(&start, true)?));
+                }
+                Event::End(_) => {
+                    self.depth = self.depth.saturating_sub(1);
+                    return Ok(None);
+                }
+                Event::Eof if self.depth > 0 => {
+                    return Err(std::io::Error::from(ErrorKind::UnexpectedEof).into());
+                }
+                Event::Eof => return Ok(None),
+                _ => continue,
+            }
+        }
+    }
+
+    /// Returns the text content of `element` without leading and trailing whitespace, child
+    /// elements are skipped.
+    pub(crate) fn read_text(&mut self, element: &XmlElement) -> Result<String> {
+        let mut text = String::new();
+        if element.is_empty {
+            return Ok(text);
+        }
+        // Whitespace next to entity references is part of the text
+        self.reader.config_mut().trim_text(false);
+        let result = self.read_text_events(&mut text);
+        self.reader.config_mut().trim_text(true);
+        result?;
+        Ok(text.trim().to_string())
+    }
+
+    fn read_text_events(&mut self, text: &mut String) -> Result<()> {
+        loop {
+            self.buf.clear();
+            match self.reader.read_event_into(&mut self.buf)? {
+                Event::Text(t) => text.push_str(&t.xml10_content()),
+                Event::CData(c) => text.push_str(&c.xml10_content()),
+                Event::GeneralRef(r) => match r.resolve_char_ref()? {
+                    Some(c) => text.push(c),
+                    None => {
+                        let entity = r.xml10_content();
+                        let resolved = resolve_xml_entity(&entity).ok_or_else(|| {
+                            quick_xml::Error::from(EscapeError::UnrecognizedEntity(
+                                0..entity.len(),
+                                entity.to_string(),
+                            ))
+                        })?;
+                        text.push_str(resolved);
+                    }
+                },
+                Event::Start(start) => {
+                    self.depth += 1;
+                    let child = XmlElement::from_bytes_start(&start, false)?;
+                    self.skip(&child)?;
+                }
+                Event::End(_) => {
+                    self.depth = self.depth.saturating_sub(1);
+                    return Ok(());
+                }
+                Event::Eof => return Err(std::io::Error::from(ErrorKind::UnexpectedEof).into()),
+                _ => continue,
+            }
+        }
+    }
+
+    /// Skips all children of `element`, if any.
+    pub(crate) fn skip(&mut self, element: &XmlElement) -> Result<()> {
+        if !element.is_empty {
+            while let Some(child) = self.next_element()? {
+                self.skip(&child)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Escapes a value for use in attributes or text, the same way JaCoCo's `XMLElement` does, and
+/// also escapes tabs, which a parser would otherwise normalize to spaces in attribute values.
+pub(crate) fn escape(value: &str) -> Cow<'_, str> {
+    if !value.contains(['<', '>', '"', '&', '\t', '\r', '\n']) {
+        return Cow::Borrowed(value);
+    }
+    let mut escaped = String::with_capacity(value.len() + 8);
+    for c in value.chars() {
+        match c {
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '&' => escaped.push_str("&amp;"),
+            '\t' => escaped.push_str("&#x09;"),
+            '\r' => escaped.push_str("&#x0D;"),
+            '\n' => escaped.push_str("&#x0A;"),
+            _ => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}